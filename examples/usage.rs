@@ -97,7 +97,7 @@ async fn main() {
 
     let save_path = "capture_example.png";
     let start_time = Instant::now();
-    match screen_capture.capture(region3, Some(save_path)) {
+    match screen_capture.capture(region3, Some((save_path, OutputFormat::Png))) {
         Ok(capture_data) => {
             let capture_duration = start_time.elapsed();
             println!("Capture with PNG save successful! Duration: {:.3}ms", capture_duration.as_secs_f64() * 1000.0);
@@ -0,0 +1,231 @@
+//! `ScreenCapture`的异步封装：DXGI/GDI调用本身是阻塞的，这里把它们丢到
+//! Tokio的阻塞线程池上执行，这样调用方的async运行时不会被一次屏幕抓取卡住。
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+use tokio::sync::broadcast;
+
+use crate::{CaptureData, CaptureError, CaptureRegion, CaptureResult, OutputFormat, ScreenCapture};
+
+/// [`init_async`]返回的异步捕获器句柄，可以在多个任务间克隆共享
+/// （内部通过`Mutex`序列化对底层`ScreenCapture`的访问）。
+#[derive(Clone)]
+pub struct AsyncScreenCapture {
+    inner: Arc<Mutex<ScreenCapture>>,
+    process_timeout: Duration,
+    /// 正在进行中的、没有携带保存路径的抓取请求，按区域去重：
+    /// 领头请求注册一个广播发送端，后来者订阅同一个发送端等待结果，
+    /// 而不是各自发起一次后端调用。条目在领头请求完成时立即移除。
+    in_flight: Arc<Mutex<HashMap<CaptureRegion, broadcast::Sender<CaptureResult<CaptureData>>>>>,
+}
+
+impl AsyncScreenCapture {
+    /// 异步捕获指定区域，语义与[`ScreenCapture::capture`]相同。
+    /// 抓屏本身（包括阻塞任务）受`process_timeout`约束
+    /// （通过[`tokio::time::timeout`]实现），超时返回[`CaptureError::Timeout`]。
+    ///
+    /// 如果指定了`save`，编码后的文件通过[`CaptureData::save_async`]写入磁盘，
+    /// 全程走`tokio::fs`，不会阻塞async运行时的工作线程。
+    ///
+    /// 当`save`为`None`时，并发的相同`region`请求会被合并：只有第一个请求
+    /// 真正触发抓取，其余请求等待并克隆同一份结果（见[`Self::in_flight`]）。
+    /// 带保存路径的请求各自独立落盘，因此不参与合并。
+    pub async fn capture(
+        &self,
+        region: CaptureRegion,
+        save: Option<(&str, OutputFormat)>,
+    ) -> CaptureResult<CaptureData> {
+        if save.is_none() {
+            return self.capture_deduped(region).await;
+        }
+
+        self.capture_uncoalesced(region, save).await
+    }
+
+    /// 先看看有没有同一个区域正在进行中的抓取：有则订阅其广播结果；
+    /// 没有则自己成为领头请求，完成后把结果广播给所有订阅者并清理条目
+    async fn capture_deduped(&self, region: CaptureRegion) -> CaptureResult<CaptureData> {
+        let existing = {
+            let in_flight = self.in_flight.lock().expect("in_flight互斥锁中毒");
+            in_flight.get(&region).map(|sender| sender.subscribe())
+        };
+
+        if let Some(mut receiver) = existing {
+            return receiver.recv().await.unwrap_or_else(|_| {
+                Err(CaptureError::CaptureError(
+                    "等待合并中的捕获结果失败".to_string(),
+                ))
+            });
+        }
+
+        let (sender, _) = broadcast::channel(1);
+        {
+            let mut in_flight = self.in_flight.lock().expect("in_flight互斥锁中毒");
+            in_flight.insert(region, sender.clone());
+        }
+
+        let result = self.capture_uncoalesced(region, None).await;
+
+        // 先移除条目、再广播，且二者之间不释放锁：反过来的话，一个在广播和
+        // 移除之间订阅的迟到请求会拿到一个值已经发送、发送端即将被丢弃的
+        // channel，`recv()`返回`Closed`而不是正常结果。先移除再广播后，
+        // 迟到的订阅者要么在移除前拿到订阅、正常收到广播结果，要么在移除后
+        // 压根看不到这个条目、转而重新触发一次抓取——两种情况下都不会出错
+        {
+            let mut in_flight = self.in_flight.lock().expect("in_flight互斥锁中毒");
+            in_flight.remove(&region);
+            let _ = sender.send(result.clone());
+        }
+
+        result
+    }
+
+    async fn capture_uncoalesced(
+        &self,
+        region: CaptureRegion,
+        save: Option<(&str, OutputFormat)>,
+    ) -> CaptureResult<CaptureData> {
+        let inner = Arc::clone(&self.inner);
+
+        let task = tokio::task::spawn_blocking(move || {
+            let mut capture = inner.lock().expect("ScreenCapture互斥锁中毒");
+            // 用不自带超时的内部变体：这里已经用tokio::time::timeout包了一层，
+            // 不需要（也不应该）再让同步层自己算一遍process_timeout
+            capture.capture_unbounded(region, None)
+        });
+
+        let result = match tokio::time::timeout(self.process_timeout, task).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_join_error)) => Err(CaptureError::CaptureError(
+                "捕获任务异常退出".to_string(),
+            )),
+            Err(_elapsed) => Err(CaptureError::Timeout),
+        }?;
+
+        if let Some((path, format)) = save {
+            result.save_async(path, format).await?;
+        }
+
+        Ok(result)
+    }
+
+    /// 设置后续`capture()`调用的超时时间
+    pub fn set_process_timeout(&mut self, timeout: Duration) {
+        self.process_timeout = timeout;
+    }
+
+    /// 按固定节奏持续捕获`region`，以`Stream<Item = CaptureResult<TimedCapture>>`的
+    /// 形式产出每一帧。每一拍会用`interval`减去实测抓取耗时作为休眠时间，
+    /// 使有效帧率保持稳定；如果上一拍的抓取本身就超过了`interval`，
+    /// 直接丢弃这一拍而不是排队赶工，避免产生越来越深的积压。
+    pub fn capture_stream(&self, region: CaptureRegion, interval: Duration) -> CaptureStream {
+        let inner = Arc::clone(&self.inner);
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let thread_stop_flag = Arc::clone(&stop_flag);
+
+        let join_handle = std::thread::spawn(move || {
+            let mut next_tick = Instant::now() + interval;
+
+            while !thread_stop_flag.load(Ordering::SeqCst) {
+                let grab_start = Instant::now();
+                let result = {
+                    let mut capture = inner.lock().expect("ScreenCapture互斥锁中毒");
+                    // 这是一个按interval自行调度的专用线程，不需要capture()
+                    // 每一拍都再算一遍process_timeout
+                    capture.capture_unbounded(region, None)
+                };
+                let grab_duration = grab_start.elapsed();
+
+                let item = result.map(|data| TimedCapture {
+                    data,
+                    grab_duration,
+                });
+                if sender.blocking_send(item).is_err() {
+                    break; // 接收端已经被丢弃，停止后台线程
+                }
+
+                let now = Instant::now();
+                if next_tick > now {
+                    std::thread::sleep(next_tick - now);
+                    next_tick += interval;
+                } else {
+                    // 上一拍抓取耗时超过了interval，丢弃落后的拍数而不是排队赶工
+                    next_tick = now + interval;
+                }
+            }
+        });
+
+        CaptureStream {
+            receiver,
+            stop_flag,
+            join_handle: Some(join_handle),
+        }
+    }
+}
+
+/// [`AsyncScreenCapture::capture_stream`]产出的单帧结果，附带本次抓取的实测耗时
+pub struct TimedCapture {
+    pub data: CaptureData,
+    pub grab_duration: Duration,
+}
+
+/// [`AsyncScreenCapture::capture_stream`]返回的帧流，同时也是停止句柄：
+/// 调用[`CaptureStream::stop`]或直接丢弃都会结束后台捕获线程。
+pub struct CaptureStream {
+    receiver: tokio::sync::mpsc::Receiver<CaptureResult<TimedCapture>>,
+    stop_flag: Arc<AtomicBool>,
+    join_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl CaptureStream {
+    /// 停止后台捕获线程，并阻塞等待其退出
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        // 关闭接收端，让阻塞在`blocking_send`上的后台线程立即返回错误并退出
+        self.receiver.close();
+        if let Some(handle) = self.join_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Stream for CaptureStream {
+    type Item = CaptureResult<TimedCapture>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 异步初始化捕获器：在阻塞线程池上完成DXGI/GDI初始化，
+/// 避免在async运行时的工作线程上直接执行阻塞的Win32调用。
+pub async fn init_async() -> CaptureResult<AsyncScreenCapture> {
+    let capture = tokio::task::spawn_blocking(|| {
+        let mut capture = ScreenCapture::new();
+        capture.init()?;
+        Ok::<ScreenCapture, CaptureError>(capture)
+    })
+    .await
+    .map_err(|_join_error| {
+        CaptureError::InitializationError("初始化任务异常退出".to_string())
+    })??;
+
+    Ok(AsyncScreenCapture {
+        inner: Arc::new(Mutex::new(capture)),
+        process_timeout: crate::capture::DEFAULT_PROCESS_TIMEOUT,
+        in_flight: Arc::new(Mutex::new(HashMap::new())),
+    })
+}
@@ -1,25 +1,35 @@
-use std::time::Instant;
-use windows::Win32::Foundation::HWND;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{HANDLE, HWND};
 use windows::Win32::Graphics::Direct3D::{D3D_DRIVER_TYPE, D3D_FEATURE_LEVEL};
 use windows::Win32::Graphics::Direct3D11::{
     D3D11_CREATE_DEVICE_FLAG, D3D11_MAP, D3D11_MAPPED_SUBRESOURCE, D3D11_SDK_VERSION,
     D3D11_TEXTURE2D_DESC, D3D11_USAGE, D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext,
     ID3D11Texture2D,
 };
-use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
+use windows::Win32::Graphics::Dxgi::Common::{DXGI_FORMAT, DXGI_SAMPLE_DESC};
 use windows::Win32::Graphics::Dxgi::{
-    DXGI_OUTDUPL_FRAME_INFO, IDXGIAdapter, IDXGIDevice, IDXGIOutput, IDXGIOutput1,
-    IDXGIOutputDuplication, IDXGIResource,
+    DXGI_ERROR_ACCESS_DENIED, DXGI_ERROR_ACCESS_LOST, DXGI_ERROR_NOT_FOUND,
+    DXGI_ERROR_WAIT_TIMEOUT, DXGI_MODE_ROTATION, DXGI_OUTDUPL_FRAME_INFO, DXGI_OUTDUPL_MOVE_RECT,
+    DXGI_OUTDUPL_POINTER_SHAPE_INFO, DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR,
+    DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR, DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME,
+    DXGI_OUTPUT_DESC, DXGI_SHARED_RESOURCE_READ, DXGI_SHARED_RESOURCE_WRITE, IDXGIAdapter,
+    IDXGIDevice, IDXGIFactory1, IDXGIKeyedMutex, IDXGIOutput, IDXGIOutput1, IDXGIOutputDuplication,
+    IDXGIResource, IDXGIResource1,
 };
 use windows::Win32::Graphics::Gdi::{
     BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BitBlt, CreateCompatibleBitmap, CreateCompatibleDC,
     DIB_RGB_COLORS, DeleteDC, DeleteObject, GetDC, ReleaseDC, SRCCOPY, SelectObject,
 };
+use windows::Win32::Foundation::RECT;
 use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
 use windows_core::Interface;
 
+use crate::metrics::{MetricsGuard, Operation};
+
 /// 捕获区域结构体
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CaptureRegion {
     pub x: i32,
     pub y: i32,
@@ -27,8 +37,26 @@ pub struct CaptureRegion {
     pub height: u32,
 }
 
+/// 单个显示输出（显示器）的信息
+#[derive(Debug, Clone)]
+pub struct OutputInfo {
+    /// 显示器在虚拟桌面坐标系中的左上角
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// 适配器索引（EnumAdapters返回的顺序）
+    pub adapter_index: u32,
+    /// 输出索引（该适配器下EnumOutputs返回的顺序）
+    pub output_index: u32,
+    /// 设备名称，例如 "\\\\.\\DISPLAY1"
+    pub device_name: String,
+    /// 旋转角度
+    pub rotation: DXGI_MODE_ROTATION,
+}
+
 /// 捕获结果
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CaptureData {
     pub data: Vec<u8>,
     pub width: u32,
@@ -36,16 +64,124 @@ pub struct CaptureData {
     pub timestamp: std::time::Instant,
 }
 
+/// 保存/编码截图时使用的图片格式及其压缩参数。
+///
+/// 高分辨率区域的原始BGRA数据相当大，PNG的无损压缩往往还是太大；
+/// JPEG/WebP/AVIF可以用有损压缩换来小一个数量级的文件体积。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 无损PNG（与此前的行为一致）
+    Png,
+    /// JPEG，`quality`范围0-100，越大质量越好、体积越大
+    Jpeg { quality: u8 },
+    /// WebP，`quality`范围0-100；`lossless`为true时忽略`quality`，使用无损模式
+    WebP { quality: u8, lossless: bool },
+    /// AVIF，`quality`范围0-100；`speed`范围0-10，越大编码越快但压缩率越低
+    Avif { quality: u8, speed: u8 },
+}
+
+impl CaptureData {
+    /// 将`data`按RGBA通道顺序解释（不做BGRA→RGBA转换），编码为`format`对应的
+    /// 文件字节，不写入磁盘。
+    pub fn encode_to(&self, format: OutputFormat) -> CaptureResult<Vec<u8>> {
+        let mut metrics_guard = MetricsGuard::start(Operation::Encode);
+
+        use image::{ImageBuffer, RgbaImage};
+
+        let img: RgbaImage = ImageBuffer::from_raw(self.width, self.height, self.data.clone())
+            .ok_or_else(|| CaptureError::CaptureError("创建图像缓冲区失败".to_string()))?;
+
+        let mut bytes = Vec::new();
+        match format {
+            OutputFormat::Png => {
+                img.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+                    .map_err(|e| CaptureError::CaptureError(format!("编码PNG失败: {}", e)))?;
+            }
+            OutputFormat::Jpeg { quality } => {
+                use image::codecs::jpeg::JpegEncoder;
+
+                // JPEG不支持Alpha通道，编码前丢弃透明度
+                let rgb = image::DynamicImage::ImageRgba8(img).to_rgb8();
+                JpegEncoder::new_with_quality(&mut bytes, quality)
+                    .encode_image(&rgb)
+                    .map_err(|e| CaptureError::CaptureError(format!("编码JPEG失败: {}", e)))?;
+            }
+            OutputFormat::WebP { quality, lossless } => {
+                if lossless {
+                    use image::codecs::webp::WebPEncoder;
+
+                    WebPEncoder::new_lossless(&mut bytes)
+                        .encode(&img, self.width, self.height, image::ExtendedColorType::Rgba8)
+                        .map_err(|e| CaptureError::CaptureError(format!("编码WebP失败: {}", e)))?;
+                } else {
+                    // image自带的WebP编码器只支持无损模式，有损压缩借助webp crate，
+                    // 复用同一个quality参数控制体积
+                    let encoder = webp::Encoder::from_rgba(&img, self.width, self.height);
+                    bytes = encoder.encode(quality as f32).to_vec();
+                }
+            }
+            OutputFormat::Avif { quality, speed } => {
+                use image::codecs::avif::AvifEncoder;
+
+                AvifEncoder::new_with_speed_quality(&mut bytes, speed, quality)
+                    .write_image(&img, self.width, self.height, image::ExtendedColorType::Rgba8)
+                    .map_err(|e| CaptureError::CaptureError(format!("编码AVIF失败: {}", e)))?;
+            }
+        }
+
+        metrics_guard.success();
+        Ok(bytes)
+    }
+
+    /// 按`format`编码并写入磁盘
+    pub fn save(&self, path: &str, format: OutputFormat) -> CaptureResult<()> {
+        let bytes = self.encode_to(format)?;
+        std::fs::write(path, bytes)
+            .map_err(|e| CaptureError::CaptureError(format!("保存文件失败: {}", e)))?;
+        Ok(())
+    }
+
+    /// `save`的异步版本：通过`tokio::fs`完成磁盘I/O，供[`crate::AsyncScreenCapture`]
+    /// 使用，避免在async运行时的工作线程上直接做阻塞写入。编码本身仍是同步的CPU操作。
+    pub async fn save_async(&self, path: &str, format: OutputFormat) -> CaptureResult<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let bytes = self.encode_to(format)?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .await
+            .map_err(|e| CaptureError::CaptureError(format!("打开文件失败: {}", e)))?;
+
+        file.write_all(&bytes)
+            .await
+            .map_err(|e| CaptureError::CaptureError(format!("写入文件失败: {}", e)))?;
+        file.flush()
+            .await
+            .map_err(|e| CaptureError::CaptureError(format!("刷新文件失败: {}", e)))?;
+        file.sync_all()
+            .await
+            .map_err(|e| CaptureError::CaptureError(format!("同步磁盘失败: {}", e)))?;
+
+        Ok(())
+    }
+}
+
 // 定义我们自己的Result类型以避免与windows crate冲突
 pub type CaptureResult<T> = std::result::Result<T, CaptureError>;
 
 /// 自定义错误类型
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CaptureError {
     InitializationError(String),
     CaptureError(String),
     InvalidRegion,
     ResourceError(String),
+    /// 捕获操作超过了配置的`process_timeout`
+    Timeout,
 }
 
 impl std::fmt::Display for CaptureError {
@@ -55,6 +191,7 @@ impl std::fmt::Display for CaptureError {
             CaptureError::CaptureError(msg) => write!(f, "捕获错误: {}", msg),
             CaptureError::InvalidRegion => write!(f, "无效的捕获区域"),
             CaptureError::ResourceError(msg) => write!(f, "资源错误: {}", msg),
+            CaptureError::Timeout => write!(f, "捕获操作超时"),
         }
     }
 }
@@ -66,40 +203,251 @@ struct DxgiResources {
     device: ID3D11Device,
     device_context: ID3D11DeviceContext,
     output_duplication: IDXGIOutputDuplication,
+    /// 保留output1以便在`DXGI_ERROR_ACCESS_LOST`后重新`DuplicateOutput`，
+    /// 而不必重建整个D3D11设备
+    output1: IDXGIOutput1,
+}
+
+/// 单帧获取结果
+enum FrameOutcome {
+    /// 获取到新的一帧原始像素数据
+    Frame(Vec<u8>),
+    /// 在超时时间内没有新的一帧（桌面静止），调用方可以廉价地轮询
+    NoNewFrame,
+}
+
+/// 缓存的硬件鼠标指针形状（仅在`PointerShapeBufferSize > 0`的帧更新）
+struct PointerShapeCache {
+    shape_type: u32,
+    width: u32,
+    height: u32,
+    pitch: u32,
+    /// 原始形状数据（含义取决于`shape_type`，见`composite_cursor`）
+    data: Vec<u8>,
+}
+
+/// 最近一次帧携带的指针位置信息
+#[derive(Debug, Clone, Copy)]
+struct PointerPosition {
+    x: i32,
+    y: i32,
+    visible: bool,
 }
 
+/// [`ScreenCapture::capture_texture`]返回的GPU端捕获结果，避免GPU→CPU的拷贝。
+pub struct GpuFrame {
+    /// 带有`D3D11_RESOURCE_MISC_SHARED_NTHANDLE | D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX`
+    /// 标志的纹理副本，其他进程/编码器可以通过`shared_handle`打开同一块显存。
+    ///
+    /// 纹理带键控互斥体：本函数返回前已经用[`Self::consumer_key`]对应的键
+    /// 做过一次`ReleaseSync`，消费方必须先对打开的`IDXGIKeyedMutex`调用
+    /// `AcquireSync(consumer_key, ...)`才能读取纹理内容。每个`GpuFrame`只用
+    /// 一次：读取完不需要、也不应该把键释放回去给生产者（这块纹理不会被
+    /// 复用于下一帧）。
+    pub texture: ID3D11Texture2D,
+    pub shared_handle: HANDLE,
+    /// 消费方读取纹理前必须在键控互斥体上`AcquireSync`的键值
+    pub consumer_key: u64,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// [`ScreenCapture::capture_incremental`]的返回结果
+#[derive(Debug)]
+pub enum CaptureUpdate {
+    /// 完整的一帧（首次捕获、多帧累积、没有变更元数据或没有新的一帧时）
+    Full(Vec<u8>),
+    /// 仅发生变化的区域（坐标为当前输出的局部坐标）及其BGRA像素数据
+    Partial(Vec<(CaptureRegion, Vec<u8>)>),
+}
+
+/// `AcquireNextFrame`遇到`DXGI_ERROR_ACCESS_LOST`/`DXGI_ERROR_ACCESS_DENIED`时，
+/// 重建`IDXGIOutputDuplication`并重试的最大次数
+const ACCESS_LOST_MAX_RETRIES: u32 = 10;
+/// 每次重试前的等待时间
+const ACCESS_LOST_RETRY_DELAY_MS: u64 = 50;
+
 /// 捕获器结构体
 pub struct ScreenCapture {
     is_initialized: bool,
     width: u32,
     height: u32,
+    /// 当前捕获的输出在虚拟桌面坐标系中的左上角偏移
+    origin_x: i32,
+    origin_y: i32,
+    /// 要捕获的适配器/输出索引
+    adapter_index: u32,
+    output_index: u32,
     dxgi_resources: Option<DxgiResources>,
     use_gdi_fallback: bool, // 是否使用GDI备选方案
+    /// 增量捕获使用的持久化整屏后备缓冲区（BGRA，行距等于 `width * 4`）
+    back_buffer: Option<Vec<u8>>,
+    /// 是否在`capture()`返回的数据中叠加硬件鼠标指针
+    include_cursor: bool,
+    cursor_shape: Option<PointerShapeCache>,
+    cursor_position: Option<PointerPosition>,
+    /// 跨帧复用的staging纹理，避免每帧都向GPU申请/释放一块新显存
+    /// （纹理, 宽, 高, DXGI_FORMAT）
+    cached_staging_texture: Option<(ID3D11Texture2D, u32, u32, i32)>,
+    /// 单次捕获操作的超时时间，默认[`DEFAULT_PROCESS_TIMEOUT`]
+    process_timeout: Duration,
+    /// `capture()`返回/保存前依次应用的后处理步骤（裁剪/缩放/模糊/灰度化），默认为空
+    preprocess_steps: Vec<crate::preprocess::PreprocessStep>,
 }
 
+/// `process_timeout`未显式设置时的默认值：桌面合成器卡死或GPU长时间不响应时，
+/// 调用方不应该被永久挂起
+pub(crate) const DEFAULT_PROCESS_TIMEOUT: Duration = Duration::from_secs(30);
+
 impl ScreenCapture {
-    /// 创建新的捕获器实例
+    /// 创建新的捕获器实例，默认捕获适配器0的输出0（通常是主显示器）
     pub fn new() -> Self {
         Self {
             is_initialized: false,
             width: 0,
             height: 0,
+            origin_x: 0,
+            origin_y: 0,
+            adapter_index: 0,
+            output_index: 0,
             dxgi_resources: None,
             use_gdi_fallback: false,
+            back_buffer: None,
+            include_cursor: false,
+            cursor_shape: None,
+            cursor_position: None,
+            cached_staging_texture: None,
+            process_timeout: DEFAULT_PROCESS_TIMEOUT,
+            preprocess_steps: Vec::new(),
         }
     }
 
+    /// 设置单次捕获操作的超时时间（默认30秒），超过后`capture()`返回
+    /// [`CaptureError::Timeout`]而不是无限期挂起
+    pub fn set_process_timeout(&mut self, timeout: Duration) {
+        self.process_timeout = timeout;
+    }
+
+    /// 设置`capture()`返回/保存前依次应用的后处理步骤（默认为空，即不处理）。
+    /// 可以用[`crate::preprocess::PreprocessBuilder`]拼装，也可以用
+    /// [`crate::preprocess::parse_preprocess_steps`]从字符串解析。
+    pub fn set_preprocess_steps(&mut self, steps: Vec<crate::preprocess::PreprocessStep>) {
+        self.preprocess_steps = steps;
+    }
+
+    /// 开启/关闭在捕获结果中叠加硬件鼠标指针（默认关闭）。
+    /// DXGI桌面复制本身不包含鼠标指针，开启后`capture()`会在提取区域后
+    /// 将最近一次的指针形状alpha混合进`region_data`。
+    pub fn set_include_cursor(&mut self, include_cursor: bool) {
+        self.include_cursor = include_cursor;
+    }
+
+    /// 创建一个捕获器实例，指定要捕获的适配器和输出索引
+    /// （索引来自 [`ScreenCapture::list_outputs`] 返回的 [`OutputInfo`]）
+    pub fn new_with_output(adapter_index: u32, output_index: u32) -> Self {
+        Self {
+            adapter_index,
+            output_index,
+            ..Self::new()
+        }
+    }
+
+    /// 枚举系统中所有适配器上的所有输出（显示器）
+    ///
+    /// 遍历每个适配器的 `EnumOutputs`，直到返回 `DXGI_ERROR_NOT_FOUND`，
+    /// 从而支持双屏、三屏等多显示器场景。
+    pub fn list_outputs() -> CaptureResult<Vec<OutputInfo>> {
+        let factory: IDXGIFactory1 = unsafe { windows::Win32::Graphics::Dxgi::CreateDXGIFactory1() }
+            .map_err(|e| CaptureError::InitializationError(format!("创建DXGI工厂失败: {:?}", e)))?;
+
+        let mut outputs = Vec::new();
+
+        for adapter_index in 0.. {
+            let adapter: IDXGIAdapter = match unsafe { factory.EnumAdapters(adapter_index) } {
+                Ok(adapter) => adapter,
+                Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+                Err(e) => {
+                    return Err(CaptureError::InitializationError(format!(
+                        "枚举适配器失败: {:?}",
+                        e
+                    )));
+                }
+            };
+
+            for output_index in 0.. {
+                let output: IDXGIOutput = match unsafe { adapter.EnumOutputs(output_index) } {
+                    Ok(output) => output,
+                    Err(e) if e.code() == DXGI_ERROR_NOT_FOUND => break,
+                    Err(e) => {
+                        return Err(CaptureError::InitializationError(format!(
+                            "枚举输出设备失败: {:?}",
+                            e
+                        )));
+                    }
+                };
+
+                let desc: DXGI_OUTPUT_DESC = unsafe { output.GetDesc() }.map_err(|e| {
+                    CaptureError::InitializationError(format!("获取输出描述失败: {:?}", e))
+                })?;
+
+                let device_name = String::from_utf16_lossy(
+                    &desc.DeviceName[..desc
+                        .DeviceName
+                        .iter()
+                        .position(|&c| c == 0)
+                        .unwrap_or(desc.DeviceName.len())],
+                );
+
+                outputs.push(OutputInfo {
+                    x: desc.DesktopCoordinates.left,
+                    y: desc.DesktopCoordinates.top,
+                    width: (desc.DesktopCoordinates.right - desc.DesktopCoordinates.left) as u32,
+                    height: (desc.DesktopCoordinates.bottom - desc.DesktopCoordinates.top) as u32,
+                    adapter_index,
+                    output_index,
+                    device_name,
+                    rotation: desc.Rotation,
+                });
+            }
+        }
+
+        Ok(outputs)
+    }
+
     /// 初始化DXGI资源
     fn initialize_dxgi(&mut self) -> CaptureResult<DxgiResources> {
+        // 选择目标适配器：0号沿用默认的"任意硬件适配器"路径，
+        // 非0号时显式枚举，以支持多显卡/多显示器场景
+        let explicit_adapter: Option<IDXGIAdapter> = if self.adapter_index == 0 {
+            None
+        } else {
+            let factory: IDXGIFactory1 =
+                unsafe { windows::Win32::Graphics::Dxgi::CreateDXGIFactory1() }.map_err(|e| {
+                    CaptureError::InitializationError(format!("创建DXGI工厂失败: {:?}", e))
+                })?;
+            Some(
+                unsafe { factory.EnumAdapters(self.adapter_index) }.map_err(|e| {
+                    CaptureError::InitializationError(format!("枚举适配器失败: {:?}", e))
+                })?,
+            )
+        };
+
         // 创建D3D11设备
         let mut device: Option<ID3D11Device> = None;
         let mut device_context: Option<ID3D11DeviceContext> = None;
         let mut feature_level = D3D_FEATURE_LEVEL::default();
 
+        // 指定了adapter时，DriverType必须是UNKNOWN(0)，否则使用HARDWARE(1)
+        let driver_type = if explicit_adapter.is_some() {
+            D3D_DRIVER_TYPE(0)
+        } else {
+            D3D_DRIVER_TYPE(1)
+        };
+
         unsafe {
             D3D11CreateDevice(
-                None,
-                D3D_DRIVER_TYPE(1), // HARDWARE
+                explicit_adapter.as_ref(),
+                driver_type,
                 None,
                 D3D11_CREATE_DEVICE_FLAG(0x20),     // BGRA_SUPPORT
                 Some(&[D3D_FEATURE_LEVEL(0xb000)]), // LEVEL_11_0
@@ -119,18 +467,20 @@ impl ScreenCapture {
             CaptureError::InitializationError("无法获取D3D11设备上下文".to_string())
         })?;
 
-        // 获取DXGI设备
+        // 获取此设备所属的DXGI适配器（与显式指定的adapter一致）
         let dxgi_device: IDXGIDevice = device
             .cast()
             .map_err(|e| CaptureError::InitializationError(format!("获取DXGI设备失败: {:?}", e)))?;
 
-        // 获取DXGI适配器
-        let adapter: IDXGIAdapter = unsafe { dxgi_device.GetAdapter() }.map_err(|e| {
-            CaptureError::InitializationError(format!("获取DXGI适配器失败: {:?}", e))
-        })?;
+        let adapter: IDXGIAdapter = match explicit_adapter {
+            Some(adapter) => adapter,
+            None => unsafe { dxgi_device.GetAdapter() }.map_err(|e| {
+                CaptureError::InitializationError(format!("获取DXGI适配器失败: {:?}", e))
+            })?,
+        };
 
-        // 获取主输出设备（通常是主显示器）
-        let output: IDXGIOutput = unsafe { adapter.EnumOutputs(0) }
+        // 获取配置的输出设备（默认0号，即主显示器）
+        let output: IDXGIOutput = unsafe { adapter.EnumOutputs(self.output_index) }
             .map_err(|e| CaptureError::InitializationError(format!("枚举输出设备失败: {:?}", e)))?;
 
         // 获取输出描述
@@ -148,19 +498,38 @@ impl ScreenCapture {
         }
         .map_err(|e| CaptureError::InitializationError(format!("创建输出复制对象失败: {:?}", e)))?;
 
-        // 设置屏幕尺寸
+        // 设置屏幕尺寸及其在虚拟桌面坐标系中的原点
         self.width =
             (output_desc.DesktopCoordinates.right - output_desc.DesktopCoordinates.left) as u32;
         self.height =
             (output_desc.DesktopCoordinates.bottom - output_desc.DesktopCoordinates.top) as u32;
+        self.origin_x = output_desc.DesktopCoordinates.left;
+        self.origin_y = output_desc.DesktopCoordinates.top;
 
         Ok(DxgiResources {
             device,
             device_context,
             output_duplication,
+            output1,
         })
     }
 
+    /// 在`DXGI_ERROR_ACCESS_LOST`/`DXGI_ERROR_ACCESS_DENIED`后重建输出复制对象，
+    /// 复用现有的D3D11设备，而不是退回GDI或重建整个设备
+    fn recreate_output_duplication(&mut self) -> CaptureResult<()> {
+        let resources = self
+            .dxgi_resources
+            .as_mut()
+            .ok_or_else(|| CaptureError::CaptureError("DXGI资源未初始化".to_string()))?;
+
+        let new_duplication = unsafe { resources.output1.DuplicateOutput(&resources.device) }
+            .map_err(|e| CaptureError::ResourceError(format!("重建输出复制对象失败: {:?}", e)))?;
+
+        resources.output_duplication = new_duplication;
+
+        Ok(())
+    }
+
     /// 初始化捕获器
     /// 优先使用DXGI技术，失败时自动使用GDI备选方案
     pub fn init(&mut self) -> CaptureResult<()> {
@@ -168,6 +537,8 @@ impl ScreenCapture {
             return Ok(());
         }
 
+        let mut metrics_guard = MetricsGuard::start(Operation::Init);
+
         // 首先尝试DXGI初始化
         match self.initialize_dxgi() {
             Ok(dxgi_resources) => {
@@ -184,6 +555,7 @@ impl ScreenCapture {
         }
 
         self.is_initialized = true;
+        metrics_guard.success();
         Ok(())
     }
 
@@ -314,34 +686,73 @@ impl ScreenCapture {
         }
     }
 
-    /// 执行DXGI屏幕捕获
-    fn capture_frame(&mut self) -> CaptureResult<Vec<u8>> {
-        // 如果DXGI资源不存在或失效，尝试重新初始化
+    /// 获取下一帧桌面资源，遇到ACCESS_LOST/ACCESS_DENIED时重建复制对象并重试，
+    /// 返回`None`表示在超时时间内桌面没有新的一帧（而不是错误）
+    fn acquire_next_frame(
+        &mut self,
+    ) -> CaptureResult<Option<(IDXGIResource, DXGI_OUTDUPL_FRAME_INFO)>> {
         if self.dxgi_resources.is_none() {
             return Err(CaptureError::CaptureError("DXGI资源未初始化".to_string()));
         }
 
-        let resources = self.dxgi_resources.as_ref().unwrap();
-
-        // 获取桌面帧
-        let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
         let mut desktop_resource: Option<IDXGIResource> = None;
+        let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
 
-        unsafe {
-            resources
-                .output_duplication
-                .AcquireNextFrame(
+        let mut attempt = 0;
+        loop {
+            let resources = self.dxgi_resources.as_ref().unwrap();
+            let acquire_result = unsafe {
+                resources.output_duplication.AcquireNextFrame(
                     100, // 超时时间（毫秒）
                     &mut frame_info,
                     &mut desktop_resource,
                 )
-                .map_err(|e| CaptureError::CaptureError(format!("获取帧失败: {:?}", e)))?;
+            };
+
+            match acquire_result {
+                Ok(()) => break,
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => {
+                    // 桌面没有发生变化，这不是错误，调用方可以廉价地再轮询一次
+                    return Ok(None);
+                }
+                Err(e) if e.code() == DXGI_ERROR_ACCESS_LOST || e.code() == DXGI_ERROR_ACCESS_DENIED => {
+                    attempt += 1;
+                    if attempt > ACCESS_LOST_MAX_RETRIES {
+                        return Err(CaptureError::CaptureError(format!(
+                            "重建输出复制对象{}次后仍然失败: {:?}",
+                            ACCESS_LOST_MAX_RETRIES, e
+                        )));
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(
+                        ACCESS_LOST_RETRY_DELAY_MS,
+                    ));
+                    // 重建本身失败时不立即放弃：下一轮AcquireNextFrame仍会返回
+                    // ACCESS_LOST，同样计入重试次数
+                    let _ = self.recreate_output_duplication();
+                    continue;
+                }
+                Err(e) => return Err(CaptureError::CaptureError(format!("获取帧失败: {:?}", e))),
+            }
         }
 
-        // 确保我们获得了资源
         let desktop_resource = desktop_resource
             .ok_or_else(|| CaptureError::CaptureError("无法获取桌面资源".to_string()))?;
 
+        Ok(Some((desktop_resource, frame_info)))
+    }
+
+    /// 执行DXGI屏幕捕获
+    fn capture_frame(&mut self) -> CaptureResult<FrameOutcome> {
+        let Some((desktop_resource, frame_info)) = self.acquire_next_frame()? else {
+            return Ok(FrameOutcome::NoNewFrame);
+        };
+
+        if self.include_cursor {
+            self.update_cursor_state(&frame_info)?;
+        }
+
+        let resources = self.dxgi_resources.as_ref().unwrap();
+
         // 检查帧信息是否有效 (暂时注释掉，可能过于严格)
         // if frame_info.LastPresentTime == 0 && frame_info.AccumulatedFrames == 0 {
         //     return Err(CaptureError::CaptureError("获取到无效的帧信息".to_string()));
@@ -356,51 +767,20 @@ impl ScreenCapture {
         let mut texture_desc = D3D11_TEXTURE2D_DESC::default();
         unsafe { texture.GetDesc(&mut texture_desc) };
 
-        // 调试信息
-        println!(
-            "纹理格式: {}, 宽度: {}, 高度: {}, 采样数: {}",
-            texture_desc.Format.0,
+        // 复用跨帧缓存的staging纹理（尺寸/格式不变时零显存开销），
+        // 而不是像早期版本那样每帧都创建一块新的
+        let staging_texture = ensure_staging_texture(
+            &resources.device,
+            &mut self.cached_staging_texture,
             texture_desc.Width,
             texture_desc.Height,
-            texture_desc.SampleDesc.Count
-        );
-
-        // 使用源纹理的原始格式创建staging纹理
-        // 注意：staging纹理不能是多重采样的，所以强制使用Count=1
-        let staging_texture_desc = D3D11_TEXTURE2D_DESC {
-            Width: texture_desc.Width,
-            Height: texture_desc.Height,
-            MipLevels: 1,
-            ArraySize: 1,
-            Format: texture_desc.Format, // 使用原始格式
-            SampleDesc: DXGI_SAMPLE_DESC {
-                Count: 1, // staging纹理必须是非多重采样的
-                Quality: 0,
-            },
-            Usage: D3D11_USAGE(3), // STAGING
-            BindFlags: 0,
-            CPUAccessFlags: 0x10000, // READ
-            MiscFlags: 0,
-        };
-
-        let mut staging_texture: Option<ID3D11Texture2D> = None;
-        unsafe {
-            resources
-                .device
-                .CreateTexture2D(&staging_texture_desc, None, Some(&mut staging_texture))
-                .map_err(|e| {
-                    let _ = unsafe { resources.output_duplication.ReleaseFrame() };
-                    CaptureError::CaptureError(format!("创建暂存纹理失败: {:?}", e))
-                })?;
-        }
-
-        let staging_texture = staging_texture.ok_or_else(|| {
+            texture_desc.Format.0,
+        )
+        .map_err(|e| {
             let _ = unsafe { resources.output_duplication.ReleaseFrame() };
-            CaptureError::CaptureError("无法创建暂存纹理".to_string())
+            e
         })?;
 
-        println!("暂存纹理创建成功，开始复制数据...");
-
         // 复制纹理数据
         unsafe {
             resources
@@ -413,8 +793,6 @@ impl ScreenCapture {
             resources.device_context.Flush();
         }
 
-        println!("纹理复制完成，开始映射...");
-
         // 映射纹理以读取数据
         let mut mapped_resource = D3D11_MAPPED_SUBRESOURCE::default();
         unsafe {
@@ -458,20 +836,443 @@ impl ScreenCapture {
             resources.output_duplication.ReleaseFrame().ok(); // 忽略错误
         }
 
-        Ok(data)
+        Ok(FrameOutcome::Frame(data))
+    }
+
+    /// 增量捕获：利用Desktop Duplication自带的移动/脏矩形元数据，
+    /// 只拷贝真正发生变化的像素，而不是每帧都搬运整个桌面。
+    ///
+    /// 返回[`CaptureUpdate::Full`]表示首次捕获、累积了多帧（`AccumulatedFrames > 1`）、
+    /// 驱动没有提供元数据（`TotalMetadataBufferSize == 0`），或者这次没有新的一帧
+    /// （此时原样返回已有的后备缓冲区，尚无后备缓冲区时为空）；
+    /// 其余情况返回[`CaptureUpdate::Partial`]，仅包含发生变化的矩形。
+    /// 仅在DXGI模式下可用，GDI备选方案不支持增量捕获。
+    pub fn capture_incremental(&mut self) -> CaptureResult<CaptureUpdate> {
+        self.ensure_dxgi_resources()?;
+
+        if self.use_gdi_fallback {
+            return Err(CaptureError::CaptureError(
+                "增量捕获仅在DXGI模式下可用".to_string(),
+            ));
+        }
+
+        let Some((desktop_resource, frame_info)) = self.acquire_next_frame()? else {
+            // 没有新的一帧：把已有的后备缓冲区原样返回
+            return Ok(CaptureUpdate::Full(
+                self.back_buffer.clone().unwrap_or_default(),
+            ));
+        };
+
+        let resources = self.dxgi_resources.as_ref().unwrap();
+
+        let texture: ID3D11Texture2D = desktop_resource.cast().map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::CaptureError(format!("转换纹理失败: {:?}", e))
+        })?;
+
+        let mut texture_desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut texture_desc) };
+
+        let width = texture_desc.Width as usize;
+        let height = texture_desc.Height as usize;
+        let stride = width * 4; // BGRA
+        let buffer_size = stride * height;
+
+        let back_buffer_stale = self.back_buffer.as_ref().map(|b| b.len()) != Some(buffer_size);
+        if back_buffer_stale {
+            self.back_buffer = Some(vec![0u8; buffer_size]);
+        }
+
+        // 首帧、尺寸变化、累积了多帧或没有元数据时，只能做全量拷贝
+        let needs_full_frame =
+            back_buffer_stale || frame_info.AccumulatedFrames > 1 || frame_info.TotalMetadataBufferSize == 0;
+
+        // staging纹理：与capture_frame一样复用跨帧缓存
+        let staging_texture = ensure_staging_texture(
+            &resources.device,
+            &mut self.cached_staging_texture,
+            texture_desc.Width,
+            texture_desc.Height,
+            texture_desc.Format.0,
+        )
+        .map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            e
+        })?;
+
+        unsafe {
+            resources
+                .device_context
+                .CopyResource(&staging_texture, &texture);
+            resources.device_context.Flush();
+        }
+
+        // 在映射纹理之前读取移动/脏矩形：这些接口在ReleaseFrame之前均有效
+        let (move_rects, dirty_rects) = if needs_full_frame {
+            (Vec::new(), Vec::new())
+        } else {
+            self.read_frame_metadata(&frame_info)?
+        };
+
+        let mut mapped_resource = D3D11_MAPPED_SUBRESOURCE::default();
+        unsafe {
+            resources
+                .device_context
+                .Map(&staging_texture, 0, D3D11_MAP(1), 0, Some(&mut mapped_resource))
+                .map_err(|e| {
+                    let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+                    CaptureError::CaptureError(format!("映射纹理失败: {:?}", e))
+                })?;
+        }
+
+        let row_pitch = mapped_resource.RowPitch as usize;
+        let result = if needs_full_frame {
+            let back_buffer = self.back_buffer.as_mut().unwrap();
+            for y in 0..height {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        (mapped_resource.pData as *const u8).add(y * row_pitch),
+                        back_buffer.as_mut_ptr().add(y * stride),
+                        stride,
+                    );
+                }
+            }
+            CaptureUpdate::Full(back_buffer.clone())
+        } else {
+            let back_buffer = self.back_buffer.as_mut().unwrap();
+
+            // 先应用移动矩形（屏幕滚动等），再用脏矩形覆盖真正重绘的区域
+            for move_rect in &move_rects {
+                apply_move_rect(back_buffer, stride, height, move_rect);
+            }
+
+            let mut changed_tiles = Vec::with_capacity(move_rects.len() + dirty_rects.len());
+
+            // move_rects和dirty_rects是DXGI报告"这一帧变了哪里"的两种互斥方式：
+            // 被滚动挪动的内容只会出现在move_rects里，不会同时出现在
+            // dirty_rects中。如果只把dirty_rects当作变化的tile返回，消费者会
+            // 完全错过每一块被移动过的区域，渲染出挪动前的过期像素——因此这里
+            // 把每个移动矩形的目的区域也当成一块变化的tile，从刚刚应用过
+            // 移动的back_buffer里读出（移动后的像素来自原本就在back_buffer里
+            // 的旧帧数据，不在这一帧新映射的纹理里，只能从back_buffer取）
+            for move_rect in &move_rects {
+                let dest = move_rect.DestinationRect;
+                let rect_width = (dest.right - dest.left).max(0) as usize;
+                let rect_height = (dest.bottom - dest.top).max(0) as usize;
+                if rect_width == 0
+                    || rect_height == 0
+                    || dest.top as usize + rect_height > height
+                {
+                    continue;
+                }
+
+                let mut tile = vec![0u8; rect_width * 4 * rect_height];
+                for row in 0..rect_height {
+                    let src_y = dest.top as usize + row;
+                    let src_offset = src_y * stride + dest.left as usize * 4;
+                    let dst_offset = row * rect_width * 4;
+                    tile[dst_offset..dst_offset + rect_width * 4]
+                        .copy_from_slice(&back_buffer[src_offset..src_offset + rect_width * 4]);
+                }
+
+                changed_tiles.push((
+                    CaptureRegion {
+                        x: dest.left,
+                        y: dest.top,
+                        width: rect_width as u32,
+                        height: rect_height as u32,
+                    },
+                    tile,
+                ));
+            }
+
+            for rect in &dirty_rects {
+                let rect_width = (rect.right - rect.left).max(0) as usize;
+                let rect_height = (rect.bottom - rect.top).max(0) as usize;
+                if rect_width == 0 || rect_height == 0 {
+                    continue;
+                }
+
+                let mut tile = vec![0u8; rect_width * 4 * rect_height];
+                for row in 0..rect_height {
+                    let src_y = rect.top as usize + row;
+                    let src_offset = src_y * row_pitch + rect.left as usize * 4;
+                    let dst_offset = row * rect_width * 4;
+                    unsafe {
+                        std::ptr::copy_nonoverlapping(
+                            (mapped_resource.pData as *const u8).add(src_offset),
+                            tile.as_mut_ptr().add(dst_offset),
+                            rect_width * 4,
+                        );
+                    }
+
+                    let dst_y = rect.top as usize + row;
+                    let dst_row_start = dst_y * stride + rect.left as usize * 4;
+                    back_buffer[dst_row_start..dst_row_start + rect_width * 4]
+                        .copy_from_slice(&tile[dst_offset..dst_offset + rect_width * 4]);
+                }
+
+                changed_tiles.push((
+                    CaptureRegion {
+                        x: rect.left,
+                        y: rect.top,
+                        width: rect_width as u32,
+                        height: rect_height as u32,
+                    },
+                    tile,
+                ));
+            }
+
+            CaptureUpdate::Partial(changed_tiles)
+        };
+
+        unsafe {
+            resources.device_context.Unmap(&staging_texture, 0);
+            resources.output_duplication.ReleaseFrame().ok();
+        }
+
+        Ok(result)
+    }
+
+    /// 从已获取的帧中读取移动矩形与脏矩形（必须在`ReleaseFrame`之前调用）
+    fn read_frame_metadata(
+        &self,
+        frame_info: &DXGI_OUTDUPL_FRAME_INFO,
+    ) -> CaptureResult<(Vec<DXGI_OUTDUPL_MOVE_RECT>, Vec<RECT>)> {
+        let resources = self.dxgi_resources.as_ref().unwrap();
+
+        let move_rect_capacity = (frame_info.TotalMetadataBufferSize as usize)
+            / std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>();
+        let mut move_rects = vec![DXGI_OUTDUPL_MOVE_RECT::default(); move_rect_capacity.max(1)];
+        let move_rects_used = unsafe {
+            resources
+                .output_duplication
+                .GetFrameMoveRects(&mut move_rects)
+        }
+        .map_err(|e| CaptureError::CaptureError(format!("获取移动矩形失败: {:?}", e)))?;
+        move_rects.truncate(
+            move_rects_used as usize / std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>(),
+        );
+
+        let dirty_rect_capacity =
+            (frame_info.TotalMetadataBufferSize as usize) / std::mem::size_of::<RECT>();
+        let mut dirty_rects = vec![RECT::default(); dirty_rect_capacity.max(1)];
+        let dirty_rects_used = unsafe {
+            resources
+                .output_duplication
+                .GetFrameDirtyRects(&mut dirty_rects)
+        }
+        .map_err(|e| CaptureError::CaptureError(format!("获取脏矩形失败: {:?}", e)))?;
+        dirty_rects.truncate(dirty_rects_used as usize / std::mem::size_of::<RECT>());
+
+        Ok((move_rects, dirty_rects))
+    }
+
+    /// 记录最近一次的指针位置，并在驱动提供了新形状数据时刷新形状缓存
+    /// （`PointerShapeBufferSize > 0`时形状才会变化，比如切换到文本光标）
+    fn update_cursor_state(&mut self, frame_info: &DXGI_OUTDUPL_FRAME_INFO) -> CaptureResult<()> {
+        // `LastMouseUpdateTime`为0时这一帧的`PointerPosition`无效，保留上一次的位置
+        if frame_info.LastMouseUpdateTime != 0 {
+            self.cursor_position = Some(PointerPosition {
+                x: frame_info.PointerPosition.Position.x,
+                y: frame_info.PointerPosition.Position.y,
+                visible: frame_info.PointerPosition.Visible.as_bool(),
+            });
+        }
+
+        if frame_info.PointerShapeBufferSize == 0 {
+            return Ok(());
+        }
+
+        let resources = self.dxgi_resources.as_ref().unwrap();
+        let mut buffer = vec![0u8; frame_info.PointerShapeBufferSize as usize];
+        let mut shape_info = DXGI_OUTDUPL_POINTER_SHAPE_INFO::default();
+        let mut size_required = 0u32;
+
+        unsafe {
+            resources.output_duplication.GetFramePointerShape(
+                buffer.len() as u32,
+                buffer.as_mut_ptr() as *mut _,
+                &mut size_required,
+                &mut shape_info,
+            )
+        }
+        .map_err(|e| CaptureError::CaptureError(format!("获取指针形状失败: {:?}", e)))?;
+
+        buffer.truncate(size_required as usize);
+
+        self.cursor_shape = Some(PointerShapeCache {
+            shape_type: shape_info.Type,
+            width: shape_info.Width,
+            height: shape_info.Height,
+            pitch: shape_info.Pitch,
+            // `shape_info.HotSpot`只是信息性的逻辑点击点，不是blit位置的一部分，
+            // 见`composite_cursor`里关于`position`本身即为左上角的说明，故不缓存
+            data: buffer,
+        });
+
+        Ok(())
+    }
+
+    /// 把缓存的鼠标指针叠加到`region_data`上（BGRA，行距为`region.width * 4`）。
+    /// `region`使用与`region_data`相同的局部坐标系；超出区域边界的部分会被裁剪。
+    fn composite_cursor(&self, region: CaptureRegion, region_data: &mut [u8]) {
+        let (Some(position), Some(shape)) = (&self.cursor_position, &self.cursor_shape) else {
+            return;
+        };
+        if !position.visible {
+            return;
+        }
+
+        // `position`已经是指针位图左上角在输出局部坐标系中的blit位置，
+        // `hotspot`只是信息性的（指示图标里哪个像素对应逻辑点击点），
+        // 不能从中减去，否则所有非零hotspot的指针（I型光标、十字、手型）
+        // 都会被往左上方向错误地平移
+        let cursor_x = position.x;
+        let cursor_y = position.y;
+        let region_stride = region.width as usize * 4;
+
+        match shape.shape_type {
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_COLOR.0 as u32 => {
+                for row in 0..shape.height as usize {
+                    let dst_y = cursor_y + row as i32 - region.y;
+                    if dst_y < 0 || dst_y as u32 >= region.height {
+                        continue;
+                    }
+                    for col in 0..shape.width as usize {
+                        let dst_x = cursor_x + col as i32 - region.x;
+                        if dst_x < 0 || dst_x as u32 >= region.width {
+                            continue;
+                        }
+                        let src = row * shape.pitch as usize + col * 4;
+                        if src + 4 > shape.data.len() {
+                            continue;
+                        }
+                        let dst = dst_y as usize * region_stride + dst_x as usize * 4;
+                        alpha_blend_bgra(&mut region_data[dst..dst + 4], &shape.data[src..src + 4]);
+                    }
+                }
+            }
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MONOCHROME.0 as u32 => {
+                // 上半部分是AND掩码，下半部分是XOR掩码，每像素1位，行距为`pitch`
+                let mask_height = (shape.height / 2) as usize;
+                for row in 0..mask_height {
+                    let dst_y = cursor_y + row as i32 - region.y;
+                    if dst_y < 0 || dst_y as u32 >= region.height {
+                        continue;
+                    }
+                    for col in 0..shape.width as usize {
+                        let dst_x = cursor_x + col as i32 - region.x;
+                        if dst_x < 0 || dst_x as u32 >= region.width {
+                            continue;
+                        }
+                        let and_byte_offset = row * shape.pitch as usize + col / 8;
+                        let xor_byte_offset =
+                            (mask_height + row) * shape.pitch as usize + col / 8;
+                        if xor_byte_offset >= shape.data.len() {
+                            continue;
+                        }
+                        let bit = 7 - (col % 8);
+                        let and_bit = (shape.data[and_byte_offset] >> bit) & 1;
+                        let xor_bit = (shape.data[xor_byte_offset] >> bit) & 1;
+
+                        // AND=1,XOR=0 -> 保持屏幕像素不变；AND=0,XOR=0 -> 黑；
+                        // AND=0,XOR=1 -> 白；AND=1,XOR=1 -> 反色
+                        if and_bit == 1 && xor_bit == 0 {
+                            continue;
+                        }
+                        let dst = dst_y as usize * region_stride + dst_x as usize * 4;
+                        if and_bit == 0 && xor_bit == 0 {
+                            region_data[dst..dst + 3].fill(0);
+                        } else if and_bit == 0 && xor_bit == 1 {
+                            region_data[dst..dst + 3].fill(0xff);
+                        } else {
+                            for channel in 0..3 {
+                                region_data[dst + channel] = !region_data[dst + channel];
+                            }
+                        }
+                    }
+                }
+            }
+            t if t == DXGI_OUTDUPL_POINTER_SHAPE_TYPE_MASKED_COLOR.0 as u32 => {
+                for row in 0..shape.height as usize {
+                    let dst_y = cursor_y + row as i32 - region.y;
+                    if dst_y < 0 || dst_y as u32 >= region.height {
+                        continue;
+                    }
+                    for col in 0..shape.width as usize {
+                        let dst_x = cursor_x + col as i32 - region.x;
+                        if dst_x < 0 || dst_x as u32 >= region.width {
+                            continue;
+                        }
+                        let src = row * shape.pitch as usize + col * 4;
+                        if src + 4 > shape.data.len() {
+                            continue;
+                        }
+                        let mask_alpha = shape.data[src + 3];
+                        let dst = dst_y as usize * region_stride + dst_x as usize * 4;
+                        if mask_alpha == 0 {
+                            // AND掩码为0：XOR颜色直接替换屏幕像素
+                            region_data[dst..dst + 3].copy_from_slice(&shape.data[src..src + 3]);
+                        } else {
+                            // AND掩码为1：与屏幕像素异或
+                            for channel in 0..3 {
+                                region_data[dst + channel] ^= shape.data[src + channel];
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 
     /// 捕获指定区域的屏幕截图
     /// 使用DXGI技术，理论上可以达到1ms一帧的高性能
-    /// 可选参数 save_path: 指定保存路径时会将截图保存为PNG文件
+    /// 可选参数 save: 指定`(保存路径, 编码格式)`时会将截图按该格式保存到磁盘
+    ///
+    /// 受[`Self::process_timeout`]约束，超过该时长仍未拿到一帧时返回
+    /// [`CaptureError::Timeout`]，而不是无限期阻塞。
     pub fn capture(
         &mut self,
         region: CaptureRegion,
-        save_path: Option<&str>,
+        save: Option<(&str, OutputFormat)>,
+    ) -> CaptureResult<CaptureData> {
+        let deadline = Instant::now() + self.process_timeout;
+        self.capture_with_deadline(region, save, Some(deadline))
+    }
+
+    /// 与[`Self::capture`]相同，但不施加`process_timeout`：供已经自行
+    /// 限定耗时的调用方使用（例如外层套了`tokio::time::timeout`的异步路径，
+    /// 或者按固定节奏调度的流式捕获），避免重复计时
+    pub(crate) fn capture_unbounded(
+        &mut self,
+        region: CaptureRegion,
+        save: Option<(&str, OutputFormat)>,
+    ) -> CaptureResult<CaptureData> {
+        self.capture_with_deadline(region, save, None)
+    }
+
+    fn capture_with_deadline(
+        &mut self,
+        region: CaptureRegion,
+        save: Option<(&str, OutputFormat)>,
+        deadline: Option<Instant>,
     ) -> CaptureResult<CaptureData> {
+        let mut metrics_guard = MetricsGuard::start(Operation::Capture);
+
         // 确保DXGI资源可用
         self.ensure_dxgi_resources()?;
 
+        // `region`使用虚拟桌面坐标系（与list_outputs()返回的OutputInfo一致），
+        // 这里转换为当前输出的局部坐标
+        let region = CaptureRegion {
+            x: region.x - self.origin_x,
+            y: region.y - self.origin_y,
+            width: region.width,
+            height: region.height,
+        };
+
         // 验证区域参数
         if region.x < 0 || region.y < 0 || region.width == 0 || region.height == 0 {
             return Err(CaptureError::InvalidRegion);
@@ -483,10 +1284,20 @@ impl ScreenCapture {
             return Err(CaptureError::InvalidRegion);
         }
 
+        // 是否已经超过deadline：轮询循环据此提前返回CaptureError::Timeout，
+        // 而不是让调用方在合成器/GPU卡死时被永久挂起。直接比较时间戳而不是
+        // 启动一个独立的看门狗线程去sleep整个process_timeout——循环本来就在
+        // 两次AcquireNextFrame之间反复被调度回来，没有必要为此常驻一个线程
+        let is_timed_out = |deadline: Option<Instant>| {
+            deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        };
+
         // 根据初始化方式选择捕获方法
         let full_screen_data = if self.use_gdi_fallback {
+            if is_timed_out(deadline) {
+                return Err(CaptureError::Timeout);
+            }
             // 使用GDI备选方案
-            println!("使用GDI捕获屏幕区域: {:?}", region);
             self.capture_with_gdi(CaptureRegion {
                 x: 0,
                 y: 0,
@@ -494,69 +1305,55 @@ impl ScreenCapture {
                 height: self.height,
             })?
         } else {
-            // 使用DXGI捕获，失败时尝试重新初始化
-            match self.capture_frame() {
-                Ok(data) => data,
-                Err(e) => {
-                    // 如果DXGI捕获失败，切换到GDI备选方案
-                    println!("DXGI捕获失败，切换到GDI备选方案: {}", e);
-                    self.use_gdi_fallback = true;
-                    self.capture_with_gdi(CaptureRegion {
-                        x: 0,
-                        y: 0,
-                        width: self.width,
-                        height: self.height,
-                    })?
+            // 使用DXGI捕获：NoNewFrame表示桌面静止，继续轮询；
+            // 只有真正致命的错误（重建复制对象耗尽重试）才会切换到GDI备选方案。
+            // 不设固定的轮询次数上限——上限会在deadline到达之前就先耗尽，
+            // 导致超时配置形同虚设；改为每轮都和deadline比较
+            let mut dxgi_result = None;
+            let mut timed_out_during_poll = false;
+            loop {
+                if is_timed_out(deadline) {
+                    timed_out_during_poll = true;
+                    break;
+                }
+                match self.capture_frame() {
+                    Ok(FrameOutcome::Frame(data)) => {
+                        dxgi_result = Some(data);
+                        break;
+                    }
+                    Ok(FrameOutcome::NoNewFrame) => continue,
+                    Err(e) => {
+                        println!("DXGI捕获失败，切换到GDI备选方案: {}", e);
+                        self.use_gdi_fallback = true;
+                        break;
+                    }
                 }
             }
-        };
 
-        // 从全屏数据中提取指定区域
-        let bytes_per_pixel = 4; // BGRA格式
-        let full_width = self.width as usize;
-        let region_width = region.width as usize;
-        let region_height = region.height as usize;
-
-        let mut region_data = Vec::with_capacity(region_width * region_height * bytes_per_pixel);
-
-        // 计算起始位置
-        let start_x = region.x.max(0) as usize;
-        let start_y = region.y.max(0) as usize;
-
-        // 提取区域数据（逐行复制）
-        for y in 0..region_height {
-            let src_y = start_y + y;
-            if src_y >= self.height as usize {
-                break;
+            if timed_out_during_poll {
+                return Err(CaptureError::Timeout);
             }
 
-            let src_row_start = src_y * full_width * bytes_per_pixel;
-            let src_start = src_row_start + start_x * bytes_per_pixel;
-            let src_end = (src_start + region_width * bytes_per_pixel)
-                .min(src_row_start + full_width * bytes_per_pixel);
-
-            if src_start < full_screen_data.len() {
-                let copy_len = src_end.saturating_sub(src_start);
-                region_data.extend_from_slice(&full_screen_data[src_start..src_start + copy_len]);
-
-                // 如果这一行不够填充，用黑色像素填充
-                let remaining = region_width * bytes_per_pixel - copy_len;
-                region_data.extend(std::iter::repeat(0u8).take(remaining));
+            match dxgi_result {
+                Some(data) => data,
+                None if self.use_gdi_fallback => self.capture_with_gdi(CaptureRegion {
+                    x: 0,
+                    y: 0,
+                    width: self.width,
+                    height: self.height,
+                })?,
+                None => return Err(CaptureError::CaptureError(
+                    "多次轮询仍未获取到新的一帧".to_string(),
+                )),
             }
-        }
-
-        // 如果提供了保存路径，则保存为PNG文件
-        if let Some(path) = save_path {
-            use image::{ImageBuffer, RgbaImage};
+        };
 
-            // 将数据转换为RGBA图像缓冲区
-            let img: RgbaImage =
-                ImageBuffer::from_raw(region.width, region.height, region_data.clone())
-                    .ok_or_else(|| CaptureError::CaptureError("创建图像缓冲区失败".to_string()))?;
+        // 从全屏数据中提取指定区域
+        let mut region_data = extract_region_data(&full_screen_data, self.width, self.height, region);
 
-            // 保存为PNG
-            img.save(path)
-                .map_err(|e| CaptureError::CaptureError(format!("保存PNG文件失败: {}", e)))?;
+        // 叠加硬件鼠标指针（DXGI桌面复制不包含指针），裁剪到当前区域范围内
+        if self.include_cursor && !self.use_gdi_fallback {
+            self.composite_cursor(region, &mut region_data);
         }
 
         let result = CaptureData {
@@ -566,6 +1363,15 @@ impl ScreenCapture {
             timestamp: Instant::now(),
         };
 
+        // 依次应用配置的后处理步骤（裁剪/缩放/模糊/灰度化），为空时原样返回
+        let result = crate::preprocess::apply_preprocess_steps(&result, &self.preprocess_steps)?;
+
+        // 如果提供了保存路径，则按指定格式编码并保存
+        if let Some((path, format)) = save {
+            result.save(path, format)?;
+        }
+
+        metrics_guard.success();
         Ok(result)
     }
 
@@ -573,6 +1379,398 @@ impl ScreenCapture {
     pub fn is_initialized(&self) -> bool {
         self.is_initialized
     }
+
+    /// 捕获指定窗口的一帧画面，使用`Windows.Graphics.Capture`后端而不是
+    /// DXGI桌面复制。适用于单个应用窗口（包括被遮挡的部分）以及DXGI路径
+    /// 会返回黑屏的独占全屏D3D游戏。
+    ///
+    /// 复用`ScreenCapture`的DXGI设备（尚未初始化时会先自动调用[`ScreenCapture::init`]），
+    /// 处于GDI备选模式时返回错误。
+    pub fn capture_window(&mut self, hwnd: HWND) -> CaptureResult<CaptureData> {
+        self.ensure_dxgi_resources()?;
+
+        let resources = self.dxgi_resources.as_ref().ok_or_else(|| {
+            CaptureError::InitializationError(
+                "Windows.Graphics.Capture需要先完成DXGI初始化".to_string(),
+            )
+        })?;
+
+        crate::window_capture::capture_window(&resources.device, &resources.device_context, hwnd)
+    }
+
+    /// 捕获一帧并以GPU纹理的形式返回，跳过`capture()`里强制执行的
+    /// 映射+memcpy到`Vec<u8>`的GPU→CPU往返。适合直接喂给NVENC/QuickSync
+    /// 等期望GPU资源的硬件编码器：纹理带有`SHARED_NTHANDLE`标志，
+    /// 编码器所在的另一个D3D设备/进程可以通过`shared_handle`打开同一块显存。
+    ///
+    /// 纹理同时带`SHARED_KEYEDMUTEX`，消费方必须对打开的`IDXGIKeyedMutex`
+    /// 调用`AcquireSync([`GpuFrame::consumer_key`], ...)`后才能读取内容，
+    /// 否则读到的可能是拷贝未完成的数据。
+    pub fn capture_texture(&mut self) -> CaptureResult<GpuFrame> {
+        if self.use_gdi_fallback {
+            return Err(CaptureError::CaptureError(
+                "GPU纹理输出仅在DXGI模式下可用".to_string(),
+            ));
+        }
+
+        let Some((desktop_resource, _frame_info)) = self.acquire_next_frame()? else {
+            return Err(CaptureError::CaptureError("等待新的一帧超时".to_string()));
+        };
+
+        let resources = self.dxgi_resources.as_ref().unwrap();
+
+        let texture: ID3D11Texture2D = desktop_resource.cast().map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::CaptureError(format!("转换纹理失败: {:?}", e))
+        })?;
+
+        let mut texture_desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut texture_desc) };
+
+        // 带键控互斥体的可共享纹理：BindFlags=0(无需绑定到管线)，
+        // MiscFlags = SHARED_KEYEDMUTEX(0x100) | SHARED_NTHANDLE(0x800)
+        let shared_texture_desc = D3D11_TEXTURE2D_DESC {
+            Width: texture_desc.Width,
+            Height: texture_desc.Height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: texture_desc.Format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE(0), // DEFAULT
+            BindFlags: 0,
+            CPUAccessFlags: 0,
+            MiscFlags: 0x100 | 0x800,
+        };
+
+        let mut shared_texture: Option<ID3D11Texture2D> = None;
+        unsafe {
+            resources
+                .device
+                .CreateTexture2D(&shared_texture_desc, None, Some(&mut shared_texture))
+                .map_err(|e| {
+                    let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+                    CaptureError::ResourceError(format!("创建共享纹理失败: {:?}", e))
+                })?;
+        }
+        let shared_texture = shared_texture.ok_or_else(|| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::ResourceError("无法创建共享纹理".to_string())
+        })?;
+
+        // 带`SHARED_KEYEDMUTEX`的纹理要求生产者/消费者通过键值交接所有权，
+        // 否则消费方打开句柄后`AcquireSync`会永远阻塞在一把从未被释放过的锁上。
+        // 新建的键控互斥体初始键为0：这里先拿到那把初始键再写入数据，写完后
+        // `ReleaseSync`到`CONSUMER_KEY`，消费方对应`AcquireSync(CONSUMER_KEY, ...)`
+        const CONSUMER_KEY: u64 = 1;
+        let keyed_mutex: IDXGIKeyedMutex = shared_texture.cast().map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::ResourceError(format!("转换为IDXGIKeyedMutex失败: {:?}", e))
+        })?;
+        unsafe {
+            keyed_mutex.AcquireSync(0, u32::MAX).map_err(|e| {
+                let _ = resources.output_duplication.ReleaseFrame();
+                CaptureError::ResourceError(format!("获取共享纹理的键控互斥体失败: {:?}", e))
+            })?;
+        }
+
+        unsafe {
+            resources
+                .device_context
+                .CopyResource(&shared_texture, &texture);
+            resources.device_context.Flush();
+        }
+
+        unsafe {
+            keyed_mutex.ReleaseSync(CONSUMER_KEY).map_err(|e| {
+                let _ = resources.output_duplication.ReleaseFrame();
+                CaptureError::ResourceError(format!("释放共享纹理的键控互斥体失败: {:?}", e))
+            })?;
+        }
+
+        let dxgi_resource: IDXGIResource1 = shared_texture.cast().map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::ResourceError(format!("转换为IDXGIResource1失败: {:?}", e))
+        })?;
+
+        let shared_handle = unsafe {
+            dxgi_resource.CreateSharedHandle(
+                None,
+                (DXGI_SHARED_RESOURCE_READ.0 | DXGI_SHARED_RESOURCE_WRITE.0) as u32,
+                None,
+            )
+        }
+        .map_err(|e| {
+            let _ = unsafe { resources.output_duplication.ReleaseFrame() };
+            CaptureError::ResourceError(format!("创建共享句柄失败: {:?}", e))
+        })?;
+
+        unsafe {
+            resources.output_duplication.ReleaseFrame().ok();
+        }
+
+        Ok(GpuFrame {
+            texture: shared_texture,
+            shared_handle,
+            consumer_key: CONSUMER_KEY,
+            width: texture_desc.Width,
+            height: texture_desc.Height,
+        })
+    }
+
+    /// 暴露底层D3D11设备，方便调用方（例如硬件编码器）复用同一个设备
+    /// 而不是重新创建一份，从而能够直接操作[`ScreenCapture::capture_texture`]返回的纹理
+    pub fn device(&self) -> Option<&ID3D11Device> {
+        self.dxgi_resources.as_ref().map(|r| &r.device)
+    }
+
+    /// 暴露底层D3D11设备上下文，参见[`ScreenCapture::device`]
+    pub fn device_context(&self) -> Option<&ID3D11DeviceContext> {
+        self.dxgi_resources.as_ref().map(|r| &r.device_context)
+    }
+
+    /// 启动一个按目标帧率持续抓取的后台线程，每抓到新的一帧就调用一次`callback`。
+    ///
+    /// 取得`self`的所有权，在一个专用线程上运行acquire/map/release循环——这正是
+    /// `AcquireNextFrame`被设计使用的方式，而不是像[`ScreenCapture::capture`]那样
+    /// 每次都单独调用一次。桌面静止（`NoNewFrame`）时跳过这一拍，而不是重复投递
+    /// 上一帧；跟不上目标帧间隔时直接同步到下一拍，不会积压。
+    /// 返回的[`CaptureStreamHandle`]可以显式调用`stop()`，或者直接丢弃来结束线程。
+    pub fn start_stream<F>(
+        mut self,
+        region: CaptureRegion,
+        fps: u32,
+        mut callback: F,
+    ) -> CaptureResult<CaptureStreamHandle>
+    where
+        F: FnMut(&CaptureData) + Send + 'static,
+    {
+        if fps == 0 {
+            return Err(CaptureError::InvalidRegion);
+        }
+
+        self.ensure_dxgi_resources()?;
+
+        let frame_interval = Duration::from_secs_f64(1.0 / fps as f64);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let thread_stop_flag = Arc::clone(&stop_flag);
+
+        let join_handle = std::thread::spawn(move || {
+            let mut next_tick = Instant::now() + frame_interval;
+
+            while !thread_stop_flag.load(Ordering::SeqCst) {
+                match self.capture_frame() {
+                    Ok(FrameOutcome::Frame(full_screen_data)) => {
+                        let region = CaptureRegion {
+                            x: region.x - self.origin_x,
+                            y: region.y - self.origin_y,
+                            width: region.width,
+                            height: region.height,
+                        };
+                        let mut region_data =
+                            extract_region_data(&full_screen_data, self.width, self.height, region);
+                        if self.include_cursor {
+                            self.composite_cursor(region, &mut region_data);
+                        }
+                        let data = CaptureData {
+                            data: region_data,
+                            width: region.width,
+                            height: region.height,
+                            timestamp: Instant::now(),
+                        };
+                        callback(&data);
+                    }
+                    Ok(FrameOutcome::NoNewFrame) => {
+                        // 桌面静止：跳过这一拍，不重复投递上一帧
+                    }
+                    Err(_) => break, // 致命错误，结束流
+                }
+
+                let now = Instant::now();
+                if next_tick > now {
+                    std::thread::sleep(next_tick - now);
+                    next_tick += frame_interval;
+                } else {
+                    // 跟不上目标帧率，丢弃落后的拍数而不是排队赶工
+                    next_tick = now + frame_interval;
+                }
+            }
+        });
+
+        Ok(CaptureStreamHandle {
+            stop_flag,
+            join_handle: Some(join_handle),
+        })
+    }
+}
+
+/// [`ScreenCapture::start_stream`]返回的控制句柄。
+/// `Drop`时会自动停止后台线程并等待其退出。
+pub struct CaptureStreamHandle {
+    stop_flag: Arc<AtomicBool>,
+    join_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl CaptureStreamHandle {
+    /// 停止后台捕获线程，并阻塞等待其退出
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.join_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for CaptureStreamHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 将一个移动矩形应用到后备缓冲区：把`move_rect.SourcePoint`处的像素块
+/// 搬到`move_rect.DestinationRect`描述的位置，正确处理源/目的重叠的情况
+/// （按源/目的的相对位置决定逐行拷贝方向，等价于`memmove`）。
+fn apply_move_rect(
+    back_buffer: &mut [u8],
+    stride: usize,
+    buffer_height: usize,
+    move_rect: &DXGI_OUTDUPL_MOVE_RECT,
+) {
+    let dest = move_rect.DestinationRect;
+    let rect_width = (dest.right - dest.left).max(0) as usize;
+    let rect_height = (dest.bottom - dest.top).max(0) as usize;
+    if rect_width == 0 || rect_height == 0 {
+        return;
+    }
+
+    let src_x = move_rect.SourcePoint.x as usize;
+    let src_y = move_rect.SourcePoint.y as usize;
+    let dest_x = dest.left as usize;
+    let dest_y = dest.top as usize;
+
+    if dest_y.max(src_y) + rect_height > buffer_height {
+        return; // 元数据异常，跳过而不是越界
+    }
+
+    let row_bytes = rect_width * 4;
+    // 目的行在源行之下时必须从最后一行往前拷贝，否则会先覆盖掉还没读取的源数据
+    let rows: Box<dyn Iterator<Item = usize>> = if dest_y > src_y {
+        Box::new((0..rect_height).rev())
+    } else {
+        Box::new(0..rect_height)
+    };
+
+    for row in rows {
+        let src_offset = (src_y + row) * stride + src_x * 4;
+        let dst_offset = (dest_y + row) * stride + dest_x * 4;
+        back_buffer.copy_within(src_offset..src_offset + row_bytes, dst_offset);
+    }
+}
+
+/// 获取（或按需创建）一块可复用的staging纹理：尺寸/格式匹配缓存时直接
+/// 克隆返回（COM引用计数自增，零显存开销），否则创建新纹理并刷新缓存。
+/// 这避免了连续捕获（[`ScreenCapture::start_stream`]等）每帧都申请/释放显存。
+fn ensure_staging_texture(
+    device: &ID3D11Device,
+    cache: &mut Option<(ID3D11Texture2D, u32, u32, i32)>,
+    width: u32,
+    height: u32,
+    format: i32,
+) -> CaptureResult<ID3D11Texture2D> {
+    if let Some((texture, cached_width, cached_height, cached_format)) = cache.as_ref() {
+        if *cached_width == width && *cached_height == height && *cached_format == format {
+            return Ok(texture.clone());
+        }
+    }
+
+    let desc = D3D11_TEXTURE2D_DESC {
+        Width: width,
+        Height: height,
+        MipLevels: 1,
+        ArraySize: 1,
+        Format: DXGI_FORMAT(format),
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1, // staging纹理必须是非多重采样的
+            Quality: 0,
+        },
+        Usage: D3D11_USAGE(3),   // STAGING
+        BindFlags: 0,
+        CPUAccessFlags: 0x10000, // READ
+        MiscFlags: 0,
+    };
+
+    let mut texture: Option<ID3D11Texture2D> = None;
+    unsafe {
+        device
+            .CreateTexture2D(&desc, None, Some(&mut texture))
+            .map_err(|e| CaptureError::CaptureError(format!("创建暂存纹理失败: {:?}", e)))?;
+    }
+    let texture =
+        texture.ok_or_else(|| CaptureError::CaptureError("无法创建暂存纹理".to_string()))?;
+
+    *cache = Some((texture.clone(), width, height, format));
+    Ok(texture)
+}
+
+/// 从整屏BGRA数据中提取`region`描述的矩形（逐行复制，超出右边界的部分用黑色填充；
+/// 超出下边界的行不输出，此时返回的数据比`region`描述的要短）。
+/// 被[`ScreenCapture::capture`]和[`ScreenCapture::start_stream`]共用。
+fn extract_region_data(
+    full_screen_data: &[u8],
+    full_width: u32,
+    full_height: u32,
+    region: CaptureRegion,
+) -> Vec<u8> {
+    let bytes_per_pixel = 4; // BGRA格式
+    let full_width = full_width as usize;
+    let region_width = region.width as usize;
+    let region_height = region.height as usize;
+
+    let mut region_data = Vec::with_capacity(region_width * region_height * bytes_per_pixel);
+
+    let start_x = region.x.max(0) as usize;
+    let start_y = region.y.max(0) as usize;
+
+    for y in 0..region_height {
+        let src_y = start_y + y;
+        if src_y >= full_height as usize {
+            break;
+        }
+
+        let src_row_start = src_y * full_width * bytes_per_pixel;
+        let src_start = src_row_start + start_x * bytes_per_pixel;
+        let src_end =
+            (src_start + region_width * bytes_per_pixel).min(src_row_start + full_width * bytes_per_pixel);
+
+        if src_start < full_screen_data.len() {
+            let copy_len = src_end.saturating_sub(src_start);
+            region_data.extend_from_slice(&full_screen_data[src_start..src_start + copy_len]);
+
+            // 如果这一行不够填充，用黑色像素填充
+            let remaining = region_width * bytes_per_pixel - copy_len;
+            region_data.extend(std::iter::repeat(0u8).take(remaining));
+        }
+    }
+
+    region_data
+}
+
+/// 将一个straight-alpha的BGRA源像素混合到目的像素上
+fn alpha_blend_bgra(dst: &mut [u8], src: &[u8]) {
+    let src_a = src[3] as u32;
+    if src_a == 0 {
+        return;
+    }
+    if src_a == 255 {
+        dst[..3].copy_from_slice(&src[..3]);
+        return;
+    }
+    for channel in 0..3 {
+        let blended = (src[channel] as u32 * src_a + dst[channel] as u32 * (255 - src_a)) / 255;
+        dst[channel] = blended as u8;
+    }
 }
 
 impl Default for ScreenCapture {
@@ -596,11 +1794,11 @@ pub fn init() -> CaptureResult<ScreenCapture> {
 }
 
 /// 便捷函数：捕获屏幕区域
-/// 可选参数 save_path: 指定保存路径时会将截图保存为PNG文件
+/// 可选参数 save: 指定`(保存路径, 编码格式)`时会将截图按该格式保存到磁盘
 pub fn capture(
     capture: &mut ScreenCapture,
     region: CaptureRegion,
-    save_path: Option<&str>,
+    save: Option<(&str, OutputFormat)>,
 ) -> CaptureResult<CaptureData> {
-    capture.capture(region, save_path)
+    capture.capture(region, save)
 }
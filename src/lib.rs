@@ -3,5 +3,12 @@
 //! This crate provides efficient screen capture capabilities using DirectX Graphics Infrastructure (DXGI)
 //! for optimal performance on Windows platforms.
 
+pub mod async_capture;
 pub mod capture;
-pub use capture::*;
\ No newline at end of file
+pub mod metrics;
+pub mod preprocess;
+mod window_capture;
+
+pub use async_capture::{AsyncScreenCapture, CaptureStream, TimedCapture, init_async};
+pub use capture::*;
+pub use preprocess::{PreprocessBuilder, PreprocessStep, parse_preprocess_steps};
\ No newline at end of file
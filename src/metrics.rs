@@ -0,0 +1,104 @@
+//! 可选的指标埋点子系统，通过`metrics` feature开关。
+//!
+//! 这里只负责“记录”，不负责“导出”：本crate自己不内置Prometheus exporter，
+//! 下游按需安装一个`metrics`生态的Recorder（例如`metrics-exporter-prometheus`），
+//! 把这些计数器/直方图转换成可以被抓取的格式。下面导出的常量就是exporter
+//! 应该关注的指标名称。关闭`metrics` feature时所有记录调用都是空操作。
+
+/// 初始化操作开始的计数器
+pub const METRIC_INIT_STARTED: &str = "luo_capture_init_started_total";
+/// 初始化操作结束的计数器，带`result`标签（`success`/`failure`）
+pub const METRIC_INIT_COMPLETED: &str = "luo_capture_init_completed_total";
+/// 初始化耗时直方图（秒）
+pub const METRIC_INIT_DURATION: &str = "luo_capture_init_duration_seconds";
+
+/// 捕获操作开始的计数器
+pub const METRIC_CAPTURE_STARTED: &str = "luo_capture_capture_started_total";
+/// 捕获操作结束的计数器，带`result`标签
+pub const METRIC_CAPTURE_COMPLETED: &str = "luo_capture_capture_completed_total";
+/// 捕获耗时直方图（秒）
+pub const METRIC_CAPTURE_DURATION: &str = "luo_capture_capture_duration_seconds";
+
+/// 编码/保存操作开始的计数器
+pub const METRIC_ENCODE_STARTED: &str = "luo_capture_encode_started_total";
+/// 编码/保存操作结束的计数器，带`result`标签
+pub const METRIC_ENCODE_COMPLETED: &str = "luo_capture_encode_completed_total";
+/// 编码/保存耗时直方图（秒）
+pub const METRIC_ENCODE_DURATION: &str = "luo_capture_encode_duration_seconds";
+
+/// [`MetricsGuard`]记录哪一组 started/completed/duration 常量
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Operation {
+    Init,
+    Capture,
+    Encode,
+}
+
+impl Operation {
+    fn names(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Operation::Init => (
+                METRIC_INIT_STARTED,
+                METRIC_INIT_COMPLETED,
+                METRIC_INIT_DURATION,
+            ),
+            Operation::Capture => (
+                METRIC_CAPTURE_STARTED,
+                METRIC_CAPTURE_COMPLETED,
+                METRIC_CAPTURE_DURATION,
+            ),
+            Operation::Encode => (
+                METRIC_ENCODE_STARTED,
+                METRIC_ENCODE_COMPLETED,
+                METRIC_ENCODE_DURATION,
+            ),
+        }
+    }
+}
+
+/// RAII埋点：创建时记录`started`计数并开始计时，`Drop`时记录耗时直方图以及
+/// `completed`计数。默认按失败（`result="failure"`）打标，调用方确认操作成功后
+/// 应调用[`MetricsGuard::success`]把最终结果改记为成功——这样函数中途因为`?`
+/// 提前返回的各个错误分支不需要逐一手动记录失败指标，天然就是"失败"。
+pub(crate) struct MetricsGuard {
+    operation: Operation,
+    start: std::time::Instant,
+    succeeded: bool,
+}
+
+impl MetricsGuard {
+    pub(crate) fn start(operation: Operation) -> Self {
+        #[cfg(feature = "metrics")]
+        {
+            let (started, _, _) = operation.names();
+            metrics::counter!(started).increment(1);
+        }
+
+        MetricsGuard {
+            operation,
+            start: std::time::Instant::now(),
+            succeeded: false,
+        }
+    }
+
+    /// 标记本次操作成功，`Drop`时按`result="success"`记录
+    pub(crate) fn success(&mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for MetricsGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        {
+            let (_, completed, duration) = self.operation.names();
+            let result = if self.succeeded { "success" } else { "failure" };
+            metrics::counter!(completed, "result" => result).increment(1);
+            metrics::histogram!(duration).record(self.start.elapsed().as_secs_f64());
+        }
+        #[cfg(not(feature = "metrics"))]
+        {
+            let _ = (&self.operation, &self.start, self.succeeded);
+        }
+    }
+}
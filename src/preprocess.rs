@@ -0,0 +1,185 @@
+//! 在`capture()`返回/保存之前对像素做的一组后处理步骤（裁剪/缩放/模糊/灰度化）。
+//!
+//! 相比捕获原始分辨率的帧、保存成文件之后再重新解码处理一遍，在捕获当时就
+//! 完成降采样或者给敏感区域打码，省掉了一次解码和一份大尺寸的中间数据。
+
+use crate::{CaptureData, CaptureError, CaptureResult};
+
+/// 应用在捕获结果上的单个处理步骤，按[`ScreenCapture::set_preprocess_steps`]中
+/// 数组的顺序依次执行
+///
+/// [`ScreenCapture::set_preprocess_steps`]: crate::ScreenCapture::set_preprocess_steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreprocessStep {
+    /// 缩放到指定尺寸
+    Resize { width: u32, height: u32 },
+    /// 裁剪出以`(x, y)`为左上角、`width x height`大小的子区域
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// 高斯模糊，`sigma`越大越模糊
+    Blur { sigma: f32 },
+    /// 转换为灰度（仍然是4通道RGBA，只是R/G/B三个分量相等）
+    Grayscale,
+}
+
+/// 拼装[`PreprocessStep`]列表的类型化构建器，链式调用后以`build()`收尾
+#[derive(Debug, Default, Clone)]
+pub struct PreprocessBuilder {
+    steps: Vec<PreprocessStep>,
+}
+
+impl PreprocessBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resize(mut self, width: u32, height: u32) -> Self {
+        self.steps.push(PreprocessStep::Resize { width, height });
+        self
+    }
+
+    pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.steps.push(PreprocessStep::Crop {
+            x,
+            y,
+            width,
+            height,
+        });
+        self
+    }
+
+    pub fn blur(mut self, sigma: f32) -> Self {
+        self.steps.push(PreprocessStep::Blur { sigma });
+        self
+    }
+
+    pub fn grayscale(mut self) -> Self {
+        self.steps.push(PreprocessStep::Grayscale);
+        self
+    }
+
+    pub fn build(self) -> Vec<PreprocessStep> {
+        self.steps
+    }
+}
+
+/// 解析`"resize=400x300|blur=2.0"`形式的字符串为一组[`PreprocessStep`]。
+/// 按`|`分隔的每一段都是`操作=参数`的形式（`grayscale`除外，它不带参数）：
+/// - `resize=WxH`
+/// - `crop=X,Y,WxH`
+/// - `blur=SIGMA`
+/// - `grayscale`
+pub fn parse_preprocess_steps(spec: &str) -> CaptureResult<Vec<PreprocessStep>> {
+    spec.split('|')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(parse_step)
+        .collect()
+}
+
+fn invalid_spec() -> CaptureError {
+    CaptureError::CaptureError("无法解析preprocess_steps字符串".to_string())
+}
+
+fn parse_step(segment: &str) -> CaptureResult<PreprocessStep> {
+    let (name, args) = match segment.split_once('=') {
+        Some((name, args)) => (name, Some(args)),
+        None => (segment, None),
+    };
+
+    match name {
+        "resize" => {
+            let (w, h) = args.ok_or_else(invalid_spec)?.split_once('x').ok_or_else(invalid_spec)?;
+            Ok(PreprocessStep::Resize {
+                width: w.parse().map_err(|_| invalid_spec())?,
+                height: h.parse().map_err(|_| invalid_spec())?,
+            })
+        }
+        "crop" => {
+            let args = args.ok_or_else(invalid_spec)?;
+            let mut parts = args.splitn(3, ',');
+            let x = parts.next().ok_or_else(invalid_spec)?;
+            let y = parts.next().ok_or_else(invalid_spec)?;
+            let size = parts.next().ok_or_else(invalid_spec)?;
+            let (w, h) = size.split_once('x').ok_or_else(invalid_spec)?;
+            Ok(PreprocessStep::Crop {
+                x: x.parse().map_err(|_| invalid_spec())?,
+                y: y.parse().map_err(|_| invalid_spec())?,
+                width: w.parse().map_err(|_| invalid_spec())?,
+                height: h.parse().map_err(|_| invalid_spec())?,
+            })
+        }
+        "blur" => Ok(PreprocessStep::Blur {
+            sigma: args.ok_or_else(invalid_spec)?.parse().map_err(|_| invalid_spec())?,
+        }),
+        "grayscale" => Ok(PreprocessStep::Grayscale),
+        _ => Err(invalid_spec()),
+    }
+}
+
+/// 依次应用`steps`，返回一份尺寸/像素都已更新的新[`CaptureData`]
+pub(crate) fn apply_preprocess_steps(
+    data: &CaptureData,
+    steps: &[PreprocessStep],
+) -> CaptureResult<CaptureData> {
+    if steps.is_empty() {
+        return Ok(CaptureData {
+            data: data.data.clone(),
+            width: data.width,
+            height: data.height,
+            timestamp: data.timestamp,
+        });
+    }
+
+    use image::{DynamicImage, GenericImageView, ImageBuffer, RgbaImage};
+
+    let img: RgbaImage = ImageBuffer::from_raw(data.width, data.height, data.data.clone())
+        .ok_or_else(|| CaptureError::CaptureError("创建图像缓冲区失败".to_string()))?;
+    let mut img = DynamicImage::ImageRgba8(img);
+
+    for step in steps {
+        img = match *step {
+            PreprocessStep::Resize { width, height } => {
+                img.resize_exact(width, height, image::imageops::FilterType::Triangle)
+            }
+            PreprocessStep::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => img.crop_imm(x, y, width, height),
+            PreprocessStep::Blur { sigma } => img.blur(sigma),
+            PreprocessStep::Grayscale => grayscale_keep_alpha(&img),
+        };
+    }
+
+    let width = img.width();
+    let height = img.height();
+
+    Ok(CaptureData {
+        data: img.to_rgba8().into_raw(),
+        width,
+        height,
+        timestamp: data.timestamp,
+    })
+}
+
+/// `DynamicImage::grayscale()`会丢弃Alpha通道，这里手动转换，
+/// 保持输出仍是4通道RGBA（R=G=B=灰度值），避免后续`encode_to`/`capture`的
+/// 调用方需要关心通道数发生了变化
+fn grayscale_keep_alpha(img: &image::DynamicImage) -> image::DynamicImage {
+    use image::{GenericImageView, ImageBuffer, Rgba};
+
+    let (width, height) = img.dimensions();
+    let mut out = ImageBuffer::new(width, height);
+    for (x, y, pixel) in img.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let gray = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as u8;
+        out.put_pixel(x, y, Rgba([gray, gray, gray, a]));
+    }
+    image::DynamicImage::ImageRgba8(out)
+}
@@ -0,0 +1,188 @@
+//! Windows.Graphics.Capture(WGC)后端：捕获单个窗口或独占全屏的D3D游戏。
+//!
+//! DXGI Desktop Duplication只能抓取整个显示器，并且在独占全屏的D3D应用下会
+//! 返回黑屏。WGC通过`GraphicsCaptureItem`直接从合成器拿到窗口/显示器的纹理，
+//! 能够绕开这两个限制，但API形状（WinRT + 事件驱动的帧池）与DXGI差异很大，
+//! 所以单独放在这个模块里，复用`ScreenCapture`已经创建好的D3D11设备。
+
+use std::time::{Duration, Instant};
+
+use windows::Foundation::TypedEventHandler;
+use windows::Graphics::Capture::{
+    Direct3D11CaptureFrame, Direct3D11CaptureFramePool, GraphicsCaptureItem, GraphicsCaptureSession,
+};
+use windows::Graphics::DirectX::DirectXPixelFormat;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Direct3D11::{
+    D3D11_MAP, D3D11_MAPPED_SUBRESOURCE, D3D11_TEXTURE2D_DESC, D3D11_USAGE, ID3D11Device,
+    ID3D11DeviceContext, ID3D11Texture2D,
+};
+use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
+use windows::Win32::Graphics::Dxgi::IDXGIDevice;
+use windows::Win32::System::WinRT::Direct3D11::CreateDirect3D11DeviceFromDXGIDevice;
+use windows::Win32::System::WinRT::Graphics::Capture::IGraphicsCaptureItemInterop;
+use windows_core::Interface;
+
+use crate::{CaptureData, CaptureError, CaptureResult};
+
+/// 每次`capture_window`等待一帧到来的超时时间
+const FRAME_WAIT_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// 捕获指定窗口的一帧画面，使用`Windows.Graphics.Capture`而不是DXGI桌面复制。
+///
+/// 与`ScreenCapture::capture`不同，这条路径能够捕获被排除在桌面复制之外的
+/// 独占全屏D3D应用，以及单个应用窗口（包括被其他窗口遮挡的部分）。
+/// `device`/`device_context`复用`ScreenCapture`已经创建好的D3D11设备，
+/// 避免为WGC再创建一套GPU资源。
+pub fn capture_window(
+    device: &ID3D11Device,
+    device_context: &ID3D11DeviceContext,
+    hwnd: HWND,
+) -> CaptureResult<CaptureData> {
+    let item = create_capture_item_for_window(hwnd)?;
+
+    let dxgi_device: IDXGIDevice = device
+        .cast()
+        .map_err(|e| CaptureError::InitializationError(format!("获取DXGI设备失败: {:?}", e)))?;
+    let winrt_device = unsafe { CreateDirect3D11DeviceFromDXGIDevice(&dxgi_device) }
+        .map_err(|e| CaptureError::InitializationError(format!("创建WinRT D3D设备失败: {:?}", e)))?;
+
+    let item_size = item
+        .Size()
+        .map_err(|e| CaptureError::CaptureError(format!("获取捕获目标尺寸失败: {:?}", e)))?;
+
+    let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
+        &winrt_device,
+        DirectXPixelFormat::B8G8R8A8UIntNormalized,
+        1, // 缓冲帧数：一个就够，我们只需要最新一帧
+        item_size,
+    )
+    .map_err(|e| CaptureError::InitializationError(format!("创建帧池失败: {:?}", e)))?;
+
+    let session = frame_pool
+        .CreateCaptureSession(&item)
+        .map_err(|e| CaptureError::InitializationError(format!("创建捕获会话失败: {:?}", e)))?;
+
+    let (sender, receiver) = std::sync::mpsc::channel::<Direct3D11CaptureFrame>();
+    frame_pool
+        .FrameArrived(&TypedEventHandler::new(move |pool: &Option<Direct3D11CaptureFramePool>, _| {
+            if let Some(pool) = pool {
+                if let Ok(frame) = pool.TryGetNextFrame() {
+                    let _ = sender.send(frame);
+                }
+            }
+            Ok(())
+        }))
+        .map_err(|e| CaptureError::CaptureError(format!("注册帧到达回调失败: {:?}", e)))?;
+
+    session
+        .StartCapture()
+        .map_err(|e| CaptureError::CaptureError(format!("启动捕获会话失败: {:?}", e)))?;
+
+    let frame = receiver
+        .recv_timeout(FRAME_WAIT_TIMEOUT)
+        .map_err(|_| CaptureError::CaptureError("等待窗口捕获帧超时".to_string()))?;
+
+    let result = read_frame_to_capture_data(device, device_context, &frame);
+
+    let _ = session.Close();
+    let _ = frame_pool.Close();
+
+    result
+}
+
+/// 通过`IGraphicsCaptureItemInterop::CreateForWindow`从HWND创建捕获目标
+fn create_capture_item_for_window(hwnd: HWND) -> CaptureResult<GraphicsCaptureItem> {
+    let interop: IGraphicsCaptureItemInterop = windows::core::factory::<
+        GraphicsCaptureItem,
+        IGraphicsCaptureItemInterop,
+    >()
+    .map_err(|e| CaptureError::InitializationError(format!("获取捕获互操作工厂失败: {:?}", e)))?;
+
+    unsafe { interop.CreateForWindow(hwnd) }
+        .map_err(|e| CaptureError::InitializationError(format!("为窗口创建捕获目标失败: {:?}", e)))
+}
+
+/// 把`Direct3D11CaptureFrame`的纹理映射出来，转换成与DXGI路径一致的BGRA `Vec<u8>`
+fn read_frame_to_capture_data(
+    device: &ID3D11Device,
+    device_context: &ID3D11DeviceContext,
+    frame: &Direct3D11CaptureFrame,
+) -> CaptureResult<CaptureData> {
+    let surface = frame
+        .Surface()
+        .map_err(|e| CaptureError::CaptureError(format!("获取帧表面失败: {:?}", e)))?;
+
+    let access: windows::Win32::Graphics::Direct3D11::IDirect3DDxgiInterfaceAccess = surface
+        .cast()
+        .map_err(|e| CaptureError::CaptureError(format!("转换表面互操作接口失败: {:?}", e)))?;
+    let texture: ID3D11Texture2D = unsafe { access.GetInterface() }
+        .map_err(|e| CaptureError::CaptureError(format!("获取底层纹理失败: {:?}", e)))?;
+
+    let mut texture_desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe { texture.GetDesc(&mut texture_desc) };
+
+    let staging_desc = D3D11_TEXTURE2D_DESC {
+        Width: texture_desc.Width,
+        Height: texture_desc.Height,
+        MipLevels: 1,
+        ArraySize: 1,
+        Format: texture_desc.Format,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            Quality: 0,
+        },
+        Usage: D3D11_USAGE(3),   // STAGING
+        BindFlags: 0,
+        CPUAccessFlags: 0x10000, // READ
+        MiscFlags: 0,
+    };
+
+    let mut staging_texture: Option<ID3D11Texture2D> = None;
+    unsafe {
+        device
+            .CreateTexture2D(&staging_desc, None, Some(&mut staging_texture))
+            .map_err(|e| CaptureError::CaptureError(format!("创建暂存纹理失败: {:?}", e)))?;
+    }
+    let staging_texture = staging_texture
+        .ok_or_else(|| CaptureError::CaptureError("无法创建暂存纹理".to_string()))?;
+
+    unsafe {
+        device_context.CopyResource(&staging_texture, &texture);
+        device_context.Flush();
+    }
+
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    unsafe {
+        device_context
+            .Map(&staging_texture, 0, D3D11_MAP(1), 0, Some(&mut mapped))
+            .map_err(|e| CaptureError::CaptureError(format!("映射纹理失败: {:?}", e)))?;
+    }
+
+    let row_pitch = mapped.RowPitch as usize;
+    let width = texture_desc.Width as usize;
+    let height = texture_desc.Height as usize;
+    let stride = width * 4;
+
+    let mut data = vec![0u8; stride * height];
+    for row in 0..height {
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                (mapped.pData as *const u8).add(row * row_pitch),
+                data.as_mut_ptr().add(row * stride),
+                stride,
+            );
+        }
+    }
+
+    unsafe {
+        device_context.Unmap(&staging_texture, 0);
+    }
+
+    Ok(CaptureData {
+        data,
+        width: texture_desc.Width,
+        height: texture_desc.Height,
+        timestamp: Instant::now(),
+    })
+}
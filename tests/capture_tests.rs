@@ -82,3 +82,72 @@ fn test_convenience_functions() {
     let default_capture = ScreenCapture::default();
     assert!(!default_capture.is_initialized());
 }
+
+/// 构造一块非纯色的4x4 RGBA测试图案，确保编码/解码不会因为数据全同而掩盖问题
+fn sample_capture_data() -> CaptureData {
+    let (width, height) = (4u32, 4u32);
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[(x * 60) as u8, (y * 60) as u8, 128, 255]);
+        }
+    }
+    CaptureData {
+        data,
+        width,
+        height,
+        timestamp: std::time::Instant::now(),
+    }
+}
+
+/// 测试PNG编码是无损的：解码后像素数据应该与原始数据逐字节相等
+#[test]
+fn test_encode_to_png_round_trip() {
+    let capture_data = sample_capture_data();
+    let bytes = capture_data.encode_to(OutputFormat::Png).expect("PNG编码失败");
+
+    let decoded = image::load_from_memory(&bytes).expect("PNG解码失败");
+    assert_eq!(decoded.width(), capture_data.width);
+    assert_eq!(decoded.height(), capture_data.height);
+    assert_eq!(decoded.to_rgba8().into_raw(), capture_data.data);
+}
+
+/// 测试JPEG编码产生可解码的文件，尺寸与原图一致（JPEG是有损的，不要求像素相等）
+#[test]
+fn test_encode_to_jpeg_round_trip() {
+    let capture_data = sample_capture_data();
+    let bytes = capture_data
+        .encode_to(OutputFormat::Jpeg { quality: 90 })
+        .expect("JPEG编码失败");
+
+    let decoded = image::load_from_memory(&bytes).expect("JPEG解码失败");
+    assert_eq!(decoded.width(), capture_data.width);
+    assert_eq!(decoded.height(), capture_data.height);
+}
+
+/// 测试WebP编码的有损/无损两种模式都能产生可解码的文件
+#[test]
+fn test_encode_to_webp_round_trip() {
+    let capture_data = sample_capture_data();
+
+    let lossless_bytes = capture_data
+        .encode_to(OutputFormat::WebP {
+            quality: 100,
+            lossless: true,
+        })
+        .expect("无损WebP编码失败");
+    let decoded = image::load_from_memory(&lossless_bytes).expect("无损WebP解码失败");
+    assert_eq!(decoded.width(), capture_data.width);
+    assert_eq!(decoded.height(), capture_data.height);
+    assert_eq!(decoded.to_rgba8().into_raw(), capture_data.data);
+
+    let lossy_bytes = capture_data
+        .encode_to(OutputFormat::WebP {
+            quality: 80,
+            lossless: false,
+        })
+        .expect("有损WebP编码失败");
+    let decoded = image::load_from_memory(&lossy_bytes).expect("有损WebP解码失败");
+    assert_eq!(decoded.width(), capture_data.width);
+    assert_eq!(decoded.height(), capture_data.height);
+}
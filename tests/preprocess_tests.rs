@@ -0,0 +1,90 @@
+use luo_capture::{PreprocessBuilder, PreprocessStep, parse_preprocess_steps};
+
+/// 测试单个resize步骤的解析
+#[test]
+fn test_parse_resize_step() {
+    let steps = parse_preprocess_steps("resize=400x300").expect("解析失败");
+    assert_eq!(steps, vec![PreprocessStep::Resize { width: 400, height: 300 }]);
+}
+
+/// 测试单个crop步骤的解析
+#[test]
+fn test_parse_crop_step() {
+    let steps = parse_preprocess_steps("crop=10,20,100x200").expect("解析失败");
+    assert_eq!(
+        steps,
+        vec![PreprocessStep::Crop {
+            x: 10,
+            y: 20,
+            width: 100,
+            height: 200,
+        }]
+    );
+}
+
+/// 测试单个blur步骤的解析
+#[test]
+fn test_parse_blur_step() {
+    let steps = parse_preprocess_steps("blur=2.5").expect("解析失败");
+    assert_eq!(steps, vec![PreprocessStep::Blur { sigma: 2.5 }]);
+}
+
+/// 测试grayscale步骤不需要参数
+#[test]
+fn test_parse_grayscale_step_no_arg() {
+    let steps = parse_preprocess_steps("grayscale").expect("解析失败");
+    assert_eq!(steps, vec![PreprocessStep::Grayscale]);
+}
+
+/// 测试用`|`分隔的多个步骤按顺序解析，且允许段之间有空白
+#[test]
+fn test_parse_multiple_steps_in_order() {
+    let steps = parse_preprocess_steps("crop=0,0,100x100 | resize=50x50 | grayscale").expect("解析失败");
+    assert_eq!(
+        steps,
+        vec![
+            PreprocessStep::Crop {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            },
+            PreprocessStep::Resize { width: 50, height: 50 },
+            PreprocessStep::Grayscale,
+        ]
+    );
+}
+
+/// 测试空字符串和只含空白段的输入返回空列表，而不是报错
+#[test]
+fn test_parse_empty_spec_yields_no_steps() {
+    assert_eq!(parse_preprocess_steps("").expect("解析失败"), vec![]);
+    assert_eq!(parse_preprocess_steps(" | | ").expect("解析失败"), vec![]);
+}
+
+/// 测试各种畸形输入都应该返回错误，而不是panic或者静默忽略
+#[test]
+fn test_parse_malformed_specs_return_err() {
+    assert!(parse_preprocess_steps("resize=400").is_err()); // 缺少'x'分隔的高度
+    assert!(parse_preprocess_steps("resize=abcx300").is_err()); // 宽度不是数字
+    assert!(parse_preprocess_steps("crop=10,20").is_err()); // 缺少WxH部分
+    assert!(parse_preprocess_steps("crop=10,20,100").is_err()); // WxH缺少'x'
+    assert!(parse_preprocess_steps("blur=").is_err()); // 缺少sigma
+    assert!(parse_preprocess_steps("blur=fast").is_err()); // sigma不是数字
+    assert!(parse_preprocess_steps("unknown_step=1").is_err()); // 未知操作名
+}
+
+/// 测试PreprocessBuilder拼出的步骤列表与对应的字符串解析结果一致
+#[test]
+fn test_builder_matches_parsed_spec() {
+    let built = PreprocessBuilder::new()
+        .crop(0, 0, 100, 100)
+        .resize(50, 50)
+        .blur(1.5)
+        .grayscale()
+        .build();
+
+    let parsed = parse_preprocess_steps("crop=0,0,100x100|resize=50x50|blur=1.5|grayscale").expect("解析失败");
+
+    assert_eq!(built, parsed);
+}